@@ -0,0 +1,120 @@
+
+extern crate bincode;
+use self::bincode::Error as BincodeError;
+
+use std::error;
+use std::fmt;
+use std::io::{Read, Write};
+
+use lfw::Solver;
+
+
+/// Version of the cache layout, to be bumped whenever `Solver` (or anything it contains)
+/// changes, so that old caches get rebuilt instead of being misread.
+pub const FORMAT_VERSION: u32 = 3;
+
+
+#[derive(Debug)]
+pub enum Error {
+    Encoding(BincodeError),
+    UnsupportedVersion(u32),
+    InconsistentSolver,
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::Encoding(ref error) => error.description(),
+            Error::UnsupportedVersion(_) => "unsupported cache version",
+            Error::InconsistentSolver => "inconsistent solver in cache",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::Encoding(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Encoding(ref error) => {
+                write!(fmt, "Unable to encode or decode cache: {}", error)
+            },
+            Error::UnsupportedVersion(ref version) => {
+                write!(fmt, "Cache version {} is not supported (expected {})", version, FORMAT_VERSION)
+            },
+            Error::InconsistentSolver => {
+                write!(fmt, "Cached solver doesn't match its own graph, the cache is probably corrupt")
+            },
+        }
+    }
+}
+
+
+/// A built `Solver` (graph and distance tables), along with a hash of the graph data it was
+/// built from so that it can be rebuilt when that data changes.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SolverCache {
+    format_version: u32,
+    source_hash: u64,
+    solver: Solver,
+}
+
+
+impl SolverCache {
+    pub fn new(solver: Solver, source_hash: u64) -> SolverCache {
+        SolverCache {
+            format_version: FORMAT_VERSION,
+            source_hash: source_hash,
+            solver: solver,
+        }
+    }
+
+    /// Hashes the graph data a solver is built from. This is FNV-1a, which (unlike the standard
+    /// library's hasher) is guaranteed to give the same result across runs and Rust versions.
+    pub fn hash_source(source: &[u8]) -> u64 {
+        source.iter().fold(0xcbf29ce484222325, |hash, byte| {
+            (hash ^ (*byte as u64)).wrapping_mul(0x100000001b3)
+        })
+    }
+
+    pub fn source_hash(&self) -> &u64 {
+        &(self.source_hash)
+    }
+
+    pub fn solver(&self) -> &Solver {
+        &(self.solver)
+    }
+
+    pub fn into_solver(self) -> Solver {
+        self.solver
+    }
+
+    pub fn to_writer<W: Write>(&self, writer: &mut W) -> Result<(), Error> {
+        bincode::serialize_into(writer, self).map_err(Error::Encoding)
+    }
+
+    pub fn from_reader<R: Read>(reader: &mut R) -> Result<SolverCache, Error> {
+        // The version is read on its own first, as the rest of the layout depends on it
+        let format_version: u32 = try!(bincode::deserialize_from(&mut *reader).map_err(Error::Encoding));
+        if format_version != FORMAT_VERSION {
+            return Err(Error::UnsupportedVersion(format_version));
+        }
+
+        let source_hash: u64 = try!(bincode::deserialize_from(&mut *reader).map_err(Error::Encoding));
+        let solver: Solver = try!(bincode::deserialize_from(&mut *reader).map_err(Error::Encoding));
+        // A cache that decodes fine can still hold tables that don't fit together, which would
+        // make the solver index out of bounds later on
+        if !solver.is_consistent() {
+            return Err(Error::InconsistentSolver);
+        }
+        Ok(SolverCache {
+            format_version: format_version,
+            source_hash: source_hash,
+            solver: solver,
+        })
+    }
+}
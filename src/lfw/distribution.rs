@@ -0,0 +1,75 @@
+
+use std::collections::BTreeMap;
+
+
+/// How likely each location is to be Jack's hideout, based on how many of the paths consistent
+/// with a night end on it.
+#[derive(Debug, Default)]
+pub struct HideoutDistribution {
+    nb_paths: u64,
+    nb_paths_per_hideout: BTreeMap<u32, u64>,
+}
+
+
+impl HideoutDistribution {
+    pub fn new() -> HideoutDistribution {
+        HideoutDistribution::default()
+    }
+
+    pub fn from_paths(paths: &[Vec<u32>]) -> HideoutDistribution {
+        let mut distribution = HideoutDistribution::new();
+        for path in paths {
+            distribution.add_path(path);
+        }
+        distribution
+    }
+
+    /// Counts a path towards the hideout it ends on. Empty paths are ignored.
+    pub fn add_path(&mut self, path: &[u32]) {
+        if let Some(hideout) = path.last() {
+            self.add_paths_for_hideout(hideout, 1);
+        }
+    }
+
+    pub fn add_paths_for_hideout(&mut self, hideout: &u32, nb_paths: u64) {
+        if nb_paths == 0 {
+            return;
+        }
+        *self.nb_paths_per_hideout.entry(*hideout).or_insert(0) += nb_paths;
+        self.nb_paths += nb_paths;
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nb_paths == 0
+    }
+
+    pub fn nb_paths(&self) -> &u64 {
+        &(self.nb_paths)
+    }
+
+    pub fn nb_paths_for_hideout(&self, hideout: &u32) -> u64 {
+        *self.nb_paths_per_hideout.get(hideout).unwrap_or(&0)
+    }
+
+    pub fn probability_for_hideout(&self, hideout: &u32) -> f64 {
+        if self.nb_paths == 0 {
+            return 0.0;
+        }
+        self.nb_paths_for_hideout(hideout) as f64 / self.nb_paths as f64
+    }
+
+    /// Returns the hideouts sorted by location
+    pub fn hideouts(&self) -> Vec<u32> {
+        self.nb_paths_per_hideout.keys().cloned().collect()
+    }
+
+    /// Returns each hideout with its probability, most likely first. Hideouts that are as likely
+    /// as each other are sorted by location.
+    pub fn sorted_by_likelihood(&self) -> Vec<(u32, f64)> {
+        let mut hideouts: Vec<(u32, u64)> = self.nb_paths_per_hideout.iter().map(|(h, n)| (*h, *n)).collect();
+        hideouts.sort_by(|&(hideout_a, nb_a), &(hideout_b, nb_b)| {
+            nb_b.cmp(&nb_a).then(hideout_a.cmp(&hideout_b))
+        });
+        hideouts.into_iter().map(|(hideout, _)| (hideout, self.probability_for_hideout(&hideout))).collect()
+    }
+}
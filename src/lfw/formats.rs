@@ -0,0 +1,126 @@
+
+extern crate serde_json;
+extern crate serde_yaml_ng as serde_yaml;
+extern crate toml;
+use self::serde_json::Error as JsonError;
+use self::serde_json::Map as JsonMap;
+use self::serde_json::Value as JsonValue;
+use self::serde_yaml::Error as YamlError;
+use self::serde_yaml::Value as YamlValue;
+
+use std::error;
+use std::fmt;
+use std::path::Path;
+
+
+/// The formats graph and game data files can be written in. They're all loaded into the same
+/// structure as JSON, so the keys and values are the same whatever the format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+
+#[derive(Debug)]
+pub enum Error {
+    InvalidJson(JsonError),
+    InvalidToml(String),
+    InvalidYaml(YamlError),
+    /// A YAML mapping key that isn't a string or a number, so can't be a JSON key
+    UnsupportedYamlKey,
+}
+
+impl error::Error for Error {
+    fn description(&self) -> &str {
+        match *self {
+            Error::InvalidJson(ref error) => error.description(),
+            Error::InvalidToml(_) => "invalid TOML",
+            Error::InvalidYaml(ref error) => error.description(),
+            Error::UnsupportedYamlKey => "unsupported YAML key",
+        }
+    }
+
+    fn cause(&self) -> Option<&error::Error> {
+        match *self {
+            Error::InvalidJson(ref error) => Some(error),
+            Error::InvalidYaml(ref error) => Some(error),
+            _ => None,
+        }
+    }
+}
+impl fmt::Display for Error {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::InvalidJson(ref error) => write!(fmt, "Invalid JSON: {}", error),
+            Error::InvalidToml(ref error) => write!(fmt, "Invalid TOML: {}", error),
+            Error::InvalidYaml(ref error) => write!(fmt, "Invalid YAML: {}", error),
+            Error::UnsupportedYamlKey => write!(fmt, "Invalid YAML: keys must be strings or numbers"),
+        }
+    }
+}
+
+
+impl DataFormat {
+    /// Returns the format with the given name (`json`, `toml` or `yaml`)
+    pub fn from_name(name: &str) -> Option<DataFormat> {
+        match name.to_lowercase().as_str() {
+            "json" => Some(DataFormat::Json),
+            "toml" => Some(DataFormat::Toml),
+            "yaml" | "yml" => Some(DataFormat::Yaml),
+            _ => None,
+        }
+    }
+
+    /// Returns the format of a file from its extension, JSON being the default
+    pub fn from_path<P: AsRef<Path>>(path: P) -> DataFormat {
+        path.as_ref().extension()
+            .and_then(|extension| extension.to_str())
+            .and_then(DataFormat::from_name)
+            .unwrap_or(DataFormat::Json)
+    }
+
+    /// Parses data written in this format into JSON
+    pub fn parse(&self, source: &str) -> Result<JsonValue, Error> {
+        match *self {
+            DataFormat::Json => serde_json::from_str(source).map_err(Error::InvalidJson),
+            DataFormat::Toml => toml::from_str(source).map_err(|e| Error::InvalidToml(e.to_string())),
+            DataFormat::Yaml => {
+                let yaml = try!(serde_yaml::from_str(source).map_err(Error::InvalidYaml));
+                DataFormat::yaml_to_json(yaml)
+            },
+        }
+    }
+
+    /// Writes JSON data out in this format
+    pub fn write(&self, json: &JsonValue) -> Result<String, Error> {
+        match *self {
+            DataFormat::Json => serde_json::to_string_pretty(json).map_err(Error::InvalidJson),
+            DataFormat::Toml => toml::to_string_pretty(json).map_err(|e| Error::InvalidToml(e.to_string())),
+            DataFormat::Yaml => serde_yaml::to_string(json).map_err(Error::InvalidYaml),
+        }
+    }
+
+    // YAML allows numbers as keys (e.g. locations in a graph), which JSON has as strings
+    fn yaml_to_json(yaml: YamlValue) -> Result<JsonValue, Error> {
+        match yaml {
+            YamlValue::Mapping(mapping) => {
+                let mut object = JsonMap::new();
+                for (key, value) in mapping {
+                    let key = match key {
+                        YamlValue::String(key) => key,
+                        YamlValue::Number(key) => key.to_string(),
+                        _ => return Err(Error::UnsupportedYamlKey),
+                    };
+                    object.insert(key, try!(DataFormat::yaml_to_json(value)));
+                }
+                Ok(JsonValue::Object(object))
+            },
+            YamlValue::Sequence(sequence) => {
+                sequence.into_iter().map(DataFormat::yaml_to_json).collect::<Result<Vec<_>, _>>().map(JsonValue::Array)
+            },
+            other => serde_json::to_value(other).map_err(Error::InvalidJson),
+        }
+    }
+}
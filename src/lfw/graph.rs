@@ -1,21 +1,36 @@
 
+extern crate serde;
 extern crate serde_json;
+use self::serde::de::DeserializeOwned;
 use self::serde_json::Error as JsonError;
 use self::serde_json::Value as JsonValue;
 use self::serde_json::from_value as parse_json_value;
 
+use std::cmp;
 use std::collections::BTreeMap;
-use std::collections::HashSet;
 use std::error;
 use std::fmt;
 use std::iter::FromIterator;
 use std::ops::Index;
 
+use lfw::{DataFormat, DataFormatError, LocationSet};
+
 
 pub const MIN_INDEX: usize = 1;
-pub const MAX_INDEX: usize = 195;
 pub const REG_MOVE_KEY: &'static str = "regular_moves";
 pub const ALLEY_MOVE_KEY: &'static str = "alleyway_moves";
+pub const CROSSINGS_KEY: &'static str = "crossings";
+pub const COORDINATES_KEY: &'static str = "coordinates";
+pub const DISTRICT_KEY: &'static str = "district";
+
+/// The board of the game, as found in `lfw-graph.json`, embedded so it's always available.
+///
+/// Crossings aren't numbered on the physical board, so this one has a crossing for each regular
+/// connection instead, numbered in ascending order of the locations they connect. Police on a
+/// crossing then block that move only, which can leave paths the real board would rule out, but
+/// never rules out one Jack could take. It has no coordinates nor districts, so the DOT export
+/// leaves the layout to Graphviz.
+pub const STANDARD_GRAPH_JSON: &'static str = include_str!("../../lfw-graph.json");
 
 
 #[derive(Debug)]
@@ -24,7 +39,11 @@ pub enum Error {
     LocationNotFound(u32),
     LocationNotParsable(u32, JsonError),
     MissingKey(String),
-    InvalidLocationValue(u32),
+    InvalidLocationValue(u32, u32),
+    NotParsable(DataFormatError),
+    InvalidCrossingValue(u32, u32),
+    /// A key that's a number, but not one a location can have
+    InvalidLocationKey(String),
 }
 
 impl error::Error for Error {
@@ -34,13 +53,17 @@ impl error::Error for Error {
             Error::LocationNotFound(_) => "location not found",
             Error::LocationNotParsable(_, ref error) => error.description(),
             Error::MissingKey(_) => "missing key",
-            Error::InvalidLocationValue(_) => "invalid location value",
+            Error::InvalidLocationValue(_, _) => "invalid location value",
+            Error::NotParsable(ref error) => error.description(),
+            Error::InvalidCrossingValue(_, _) => "invalid crossing value",
+            Error::InvalidLocationKey(_) => "invalid location key",
         }
     }
 
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             Error::LocationNotParsable(_, ref error) => Some(error),
+            Error::NotParsable(ref error) => Some(error),
             _ => None,
         }
     }
@@ -60,34 +83,209 @@ impl fmt::Display for Error {
             Error::MissingKey(ref key) => {
                 write!(fmt, "Could not find key {}", key)
             },
-            Error::InvalidLocationValue(ref loc) => {
-                write!(fmt, "Invalid location value {} (must be 1...195)", loc)
+            Error::InvalidLocationValue(ref loc, ref nb_locations) => {
+                write!(fmt, "Invalid location value {} (must be {}...{})", loc, MIN_INDEX, nb_locations)
+            },
+            Error::NotParsable(ref error) => {
+                write!(fmt, "Graph not parsable: {}", error)
+            },
+            Error::InvalidCrossingValue(ref loc, ref crossing) => {
+                write!(fmt, "Invalid crossing {} for location {} (crossings are numbered from {})", crossing, loc, MIN_INDEX)
+            },
+            Error::InvalidLocationKey(ref key) => {
+                write!(fmt, "Invalid location {} (must be {}...{})", key, MIN_INDEX, u32::MAX)
             },
         }
     }
 }
 
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionType {
+    Regular,
+    Alleyway,
+}
+
+impl fmt::Display for ConnectionType {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConnectionType::Regular => write!(fmt, "regular"),
+            ConnectionType::Alleyway => write!(fmt, "alleyway"),
+        }
+    }
+}
+
+
+/// Alleyways only go through a block, so an alleyway between two locations that are further
+/// apart than this with regular moves is most likely a transcription error.
+pub const MAX_PLAUSIBLE_ALLEYWAY_DISTANCE: u32 = 3;
+
+
+/// An inconsistency found in the board data by `Graph::validate`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ValidationFinding {
+    /// A location lists itself as one of its connections
+    SelfConnection(u32, ConnectionType),
+    /// A location lists the same connection more than once
+    DuplicateConnection(u32, u32, ConnectionType),
+    /// A location lists a connection that doesn't list it back
+    OneWayConnection(u32, u32, ConnectionType),
+    /// An alleyway connects two locations that are far apart with regular moves (`None` if they
+    /// aren't connected at all with regular moves)
+    ImplausibleAlleyway(u32, u32, Option<u32>),
+    /// A location that can't be reached or left with regular moves
+    IsolatedLocation(u32),
+    /// A regular connection between two locations that don't share any crossing, so police can
+    /// never block it. Only reported for graphs with crossings.
+    RegularMoveWithoutCrossing(u32, u32),
+    /// A crossing next to a single location, so no regular move goes through it
+    UnusedCrossing(u32),
+}
+
+impl fmt::Display for ValidationFinding {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ValidationFinding::SelfConnection(ref loc, ref cnx_type) => {
+                write!(fmt, "Location {} is listed in its own {} connections", loc, cnx_type)
+            },
+            ValidationFinding::DuplicateConnection(ref loc, ref cnx, ref cnx_type) => {
+                write!(fmt, "Location {} lists {} more than once in its {} connections", loc, cnx, cnx_type)
+            },
+            ValidationFinding::OneWayConnection(ref loc, ref cnx, ref cnx_type) => {
+                write!(fmt, "Location {} lists {} in its {} connections, but not the other way around", loc, cnx, cnx_type)
+            },
+            ValidationFinding::ImplausibleAlleyway(ref loc, ref cnx, Some(ref distance)) => {
+                write!(fmt, "Alleyway from {} to {} is implausible, they are {} regular moves apart", loc, cnx, distance)
+            },
+            ValidationFinding::ImplausibleAlleyway(ref loc, ref cnx, None) => {
+                write!(fmt, "Alleyway from {} to {} is implausible, they aren't connected with regular moves", loc, cnx)
+            },
+            ValidationFinding::IsolatedLocation(ref loc) => {
+                write!(fmt, "Location {} has no regular connection", loc)
+            },
+            ValidationFinding::RegularMoveWithoutCrossing(ref loc, ref cnx) => {
+                write!(fmt, "Regular move from {} to {} doesn't go through any crossing", loc, cnx)
+            },
+            ValidationFinding::UnusedCrossing(ref crossing) => {
+                write!(fmt, "Crossing {} is only next to one location", crossing)
+            },
+        }
+    }
+}
+
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Graph {
     // locations: Vec<Vec<u32>>,
+    nb_locations: u32,
     regular_connections: Vec<Vec<u32>>,
     alleyway_connections: Vec<Vec<u32>>,
+    // Same connections as above, as bitsets for the solver to work on
+    regular_connection_sets: Vec<LocationSet>,
+    alleyway_connection_sets: Vec<LocationSet>,
+    crossings: Vec<Vec<u32>>,
+    // Where each location sits on the physical board, and which part of it, when known
+    coordinates: Vec<Option<(f64, f64)>>,
+    districts: Vec<Option<String>>,
 }
 
 
 impl Graph {
-    fn verified_connections(all_connections: &BTreeMap<String, Vec<u32>>, key: &str) -> Result<Vec<u32>, Error> {
-        let connections = try!(all_connections
-                                .get(key)
-                                .ok_or(Error::MissingKey(key.to_string())));
-
-        connections.iter().map(|cnx| {
-            match *cnx {
-                c @ 1 ... 195 => Ok(c),
-                _ => Err(Error::InvalidLocationValue(*cnx))
+    // Parses the value for `key` in a location's data, if there's one
+    fn optional_value_for_key<T: DeserializeOwned>(location_data: &BTreeMap<String, JsonValue>, key: &str, location: u32) -> Result<Option<T>, Error> {
+        match location_data.get(key) {
+            Some(value) => parse_json_value(value.clone()).map(Some).map_err(|e| Error::LocationNotParsable(location, e)),
+            None => Ok(None),
+        }
+    }
+
+    fn connections_for_key(location_data: &BTreeMap<String, JsonValue>, key: &str, location: u32) -> Result<Vec<u32>, Error> {
+        try!(Graph::optional_value_for_key(location_data, key, location)).ok_or(Error::MissingKey(key.to_string()))
+    }
+
+    fn verify_connections(connections: &[u32], nb_locations: u32) -> Result<(), Error> {
+        match connections.iter().find(|cnx| **cnx < MIN_INDEX as u32 || **cnx > nb_locations) {
+            Some(cnx) => Err(Error::InvalidLocationValue(*cnx, nb_locations)),
+            None => Ok(()),
+        }
+    }
+
+    // Crossings are numbered from 1 like locations, but there's no fixed number of them: a graph
+    // has as many crossings as the highest one listed.
+    fn verify_crossings(location: &u32, crossings: &[u32]) -> Result<(), Error> {
+        match crossings.iter().find(|crossing| **crossing < MIN_INDEX as u32) {
+            Some(crossing) => Err(Error::InvalidCrossingValue(*location, *crossing)),
+            None => Ok(()),
+        }
+    }
+
+    // Builds a graph from the connections of each location (the first ones being location 1's),
+    // checking that there's at least one location and that all connections are valid locations.
+    fn from_connections(all_regular_connections: Vec<Vec<u32>>,
+                        all_alleyway_connections: Vec<Vec<u32>>,
+                        all_crossings: Vec<Vec<u32>>) -> Result<Graph, Error> {
+        let nb_locations = all_regular_connections.len() as u32;
+        if nb_locations < MIN_INDEX as u32 {
+            return Err(Error::LocationNotFound(MIN_INDEX as u32));
+        }
+        for connections in all_regular_connections.iter().chain(all_alleyway_connections.iter()) {
+            try!(Graph::verify_connections(connections, nb_locations));
+        }
+        for (idx, crossings) in all_crossings.iter().enumerate() {
+            try!(Graph::verify_crossings(&(idx as u32 + 1), crossings));
+        }
+
+        Ok(Graph {
+            nb_locations: nb_locations,
+            regular_connection_sets: all_regular_connections.iter().map(LocationSet::from_iter).collect(),
+            alleyway_connection_sets: all_alleyway_connections.iter().map(LocationSet::from_iter).collect(),
+            regular_connections: all_regular_connections,
+            alleyway_connections: all_alleyway_connections,
+            coordinates: vec![None; nb_locations as usize],
+            districts: vec![None; nb_locations as usize],
+            crossings: all_crossings,
+        })
+    }
+
+    /// Whether the graph's internal tables agree with each other, which is always the case for
+    /// graphs built by this crate, but not necessarily for ones decoded from elsewhere (such as a
+    /// corrupt cache).
+    pub fn is_consistent(&self) -> bool {
+        let nb_locations = self.nb_locations as usize;
+        let has_all_locations = nb_locations >= MIN_INDEX &&
+            [self.regular_connections.len(), self.alleyway_connections.len(),
+             self.regular_connection_sets.len(), self.alleyway_connection_sets.len(),
+             self.crossings.len(), self.coordinates.len(), self.districts.len()].iter().all(|len| *len == nb_locations);
+        if !has_all_locations {
+            return false;
+        }
+
+        let connections = self.regular_connections.iter().zip(self.regular_connection_sets.iter())
+            .chain(self.alleyway_connections.iter().zip(self.alleyway_connection_sets.iter()));
+        for (connection_list, connection_set) in connections {
+            if Graph::verify_connections(connection_list, self.nb_locations).is_err() ||
+                *connection_set != LocationSet::from_iter(connection_list) {
+                return false;
             }
-        }).collect::<Result<Vec<u32>, Error>>()
+        }
+        self.crossings.iter().all(|crossings| Graph::verify_crossings(&(MIN_INDEX as u32), crossings).is_ok())
+    }
+
+    /// Returns the board of the game (see `STANDARD_GRAPH_JSON`)
+    pub fn standard() -> Graph {
+        // expect() because the embedded board is known to be valid, a failure is a bug
+        let json = serde_json::from_str(STANDARD_GRAPH_JSON).expect("Embedded graph is not valid JSON");
+        Graph::from_json(json).expect("Embedded graph is not a valid graph")
+    }
+
+    /// Loads a graph written in TOML, with the same keys as in JSON
+    pub fn from_toml(source: &str) -> Result<Graph, Error> {
+        Graph::from_json(try!(DataFormat::Toml.parse(source).map_err(Error::NotParsable)))
+    }
+
+    /// Loads a graph written in YAML, with the same keys as in JSON
+    pub fn from_yaml(source: &str) -> Result<Graph, Error> {
+        Graph::from_json(try!(DataFormat::Yaml.parse(source).map_err(Error::NotParsable)))
     }
 
     pub fn from_json(json: JsonValue) -> Result<Graph, Error> {
@@ -98,63 +296,473 @@ impl Graph {
             _ => None
         }.ok_or(Error::GraphNotAnObject));
 
+        // The board is made of all the locations from 1 to the highest one found in the file, so a
+        // gap in the numbering is reported as a missing location below. Numbers that can't be a
+        // location (0, or too big for a `u32`) are reported too, instead of being ignored.
+        let mut nb_locations = 0;
+        for key in location_map.keys().filter(|key| !key.is_empty() && key.bytes().all(|b| b.is_ascii_digit())) {
+            match key.parse::<u32>() {
+                Ok(location) if location >= MIN_INDEX as u32 => nb_locations = cmp::max(nb_locations, location),
+                _ => return Err(Error::InvalidLocationKey(key.clone())),
+            }
+        }
+
         let mut all_regular_connections: Vec<Vec<u32>> = Vec::new();
         let mut all_alleyway_connections: Vec<Vec<u32>> = Vec::new();
+        let mut all_crossings: Vec<Vec<u32>> = Vec::new();
+        let mut all_coordinates: Vec<Option<(f64, f64)>> = Vec::new();
+        let mut all_districts: Vec<Option<String>> = Vec::new();
 
-        for x in 0..195 {
-            let idx = x + 1;
+        for idx in (MIN_INDEX as u32)..=nb_locations {
             let location = try!(location_map
                                 .remove( &(idx.to_string()) )
                                 .ok_or(Error::LocationNotFound(idx)));
-            let all_connections = try!(parse_json_value::<BTreeMap<String, Vec<u32>>>(location).or_else(|e| {
-                Err(Error::LocationNotParsable(idx, e))
+            let location_data = try!(parse_json_value::<BTreeMap<String, JsonValue>>(location).map_err(|e| {
+                Error::LocationNotParsable(idx, e)
             }));
 
-            let regular_cnx = try!(Graph::verified_connections(&all_connections, REG_MOVE_KEY));
+            let regular_cnx = try!(Graph::connections_for_key(&location_data, REG_MOVE_KEY, idx));
+            try!(Graph::verify_connections(&regular_cnx, nb_locations));
             all_regular_connections.push(regular_cnx);
 
-            let alleyway_cnx = try!(Graph::verified_connections(&all_connections, ALLEY_MOVE_KEY));
+            let alleyway_cnx = try!(Graph::connections_for_key(&location_data, ALLEY_MOVE_KEY, idx));
+            try!(Graph::verify_connections(&alleyway_cnx, nb_locations));
             all_alleyway_connections.push(alleyway_cnx);
+
+            // Crossings are optional, graphs without them just won't have police blocking Jack
+            // (see `is_regular_move_blocked`)
+            let crossings = try!(Graph::optional_value_for_key(&location_data, CROSSINGS_KEY, idx));
+            all_crossings.push(crossings.unwrap_or_else(Vec::new));
+
+            // Coordinates (as `[x, y]`) and district are optional too, and only used for display
+            all_coordinates.push(try!(Graph::optional_value_for_key(&location_data, COORDINATES_KEY, idx)));
+            all_districts.push(try!(Graph::optional_value_for_key(&location_data, DISTRICT_KEY, idx)));
         }
-        Ok(Graph {
-            regular_connections: all_regular_connections,
-            alleyway_connections: all_alleyway_connections,
-        })
+
+        let mut graph = try!(Graph::from_connections(all_regular_connections, all_alleyway_connections, all_crossings));
+        graph.coordinates = all_coordinates;
+        graph.districts = all_districts;
+        Ok(graph)
+    }
+
+    // Locations are numbered from 1, while our vectors are 0-indexed. Locations above the number
+    // of locations are left to the vectors' bound checks.
+    fn location_index(location: &u32) -> Option<usize> {
+        (*location as usize).checked_sub(MIN_INDEX)
+    }
+
+    pub fn nb_locations(&self) -> u32 {
+        self.nb_locations
     }
 
     pub fn connections_for_location(&self, location: &u32) -> Option<&Vec<u32>> {
-        if let loc @ 1 ... 195 = *location {
-            self.regular_connections.get((loc as usize) - 1)
-        } else {
-            None
-        }
+        Graph::location_index(location).and_then(|idx| self.regular_connections.get(idx))
     }
 
     pub fn alleyway_connections_for_location(&self, location: &u32) -> Option<&Vec<u32>> {
-        if let loc @ 1 ... 195 = *location {
-            self.alleyway_connections.get((loc as usize) - 1)
-        } else {
-            None
+        Graph::location_index(location).and_then(|idx| self.alleyway_connections.get(idx))
+    }
+
+    pub fn connection_set_for_location(&self, location: &u32) -> Option<&LocationSet> {
+        Graph::location_index(location).and_then(|idx| self.regular_connection_sets.get(idx))
+    }
+
+    pub fn alleyway_connection_set_for_location(&self, location: &u32) -> Option<&LocationSet> {
+        Graph::location_index(location).and_then(|idx| self.alleyway_connection_sets.get(idx))
+    }
+
+    /// Returns the crossings (where police pawns stand) adjacent to a location
+    pub fn crossings_for_location(&self, location: &u32) -> Option<&Vec<u32>> {
+        Graph::location_index(location).and_then(|idx| self.crossings.get(idx))
+    }
+
+    /// Returns the `(x, y)` coordinates of a location on the board, if they're known
+    pub fn coordinates_for_location(&self, location: &u32) -> Option<(f64, f64)> {
+        Graph::location_index(location).and_then(|idx| self.coordinates.get(idx).cloned().and_then(|c| c))
+    }
+
+    /// Returns the district (or quadrant) of the board a location is in, if it's known
+    pub fn district_for_location(&self, location: &u32) -> Option<&str> {
+        Graph::location_index(location).and_then(|idx| self.districts.get(idx)).and_then(|d| d.as_ref().map(|d| d.as_str()))
+    }
+
+    /// Returns the crossings a regular move between two locations goes through, i.e. the
+    /// crossings adjacent to both locations.
+    pub fn crossings_between_locations(&self, location: &u32, destination: &u32) -> Vec<u32> {
+        match (self.crossings_for_location(location), self.crossings_for_location(destination)) {
+            (Some(loc_crossings), Some(dest_crossings)) => {
+                loc_crossings.iter().filter(|c| dest_crossings.contains(c)).cloned().collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    /// Whether the graph has any crossing, i.e. whether police can block regular moves on it
+    pub fn has_crossings(&self) -> bool {
+        self.crossings.iter().any(|crossings| !crossings.is_empty())
+    }
+
+    /// Whether police standing on `police_crossings` prevent Jack from making a regular move
+    /// between two locations.
+    ///
+    /// A regular move goes through one of the crossings next to both locations, so it's blocked
+    /// only if all of them are occupied. A move between locations that don't share any crossing
+    /// can't be blocked: that's always the case on graphs without crossings, and is reported by
+    /// `validate` as a data error on graphs with crossings.
+    pub fn is_regular_move_blocked(&self, location: &u32, destination: &u32, police_crossings: &[u32]) -> bool {
+        let crossings = self.crossings_between_locations(location, destination);
+        !crossings.is_empty() && crossings.iter().all(|c| police_crossings.contains(c))
+    }
+
+    /// Returns the locations reachable with a carriage, i.e. two regular moves in a row.
+    /// Jack can't use a carriage to go back to where he started from.
+    pub fn carriage_connections_for_location(&self, location: &u32) -> Option<LocationSet> {
+        self.connection_set_for_location(location).map(|first_steps| {
+            let mut carriage_cnx = first_steps.iter().fold(LocationSet::new(), |mut cnx, first_step| {
+                cnx.union_with(&self.regular_connection_sets[(first_step as usize) - 1]);
+                cnx
+            });
+            carriage_cnx.remove(location);
+            carriage_cnx
+        })
+    }
+
+    pub fn all_connections_for_location(&self, location: &u32) -> Option<LocationSet> {
+        match (self.connection_set_for_location(location), self.alleyway_connection_set_for_location(location)) {
+            (Some(regular_cnx), Some(alleyway_cnx)) => Some(regular_cnx.union(alleyway_cnx)),
+            _ => None,
+        }
+    }
+}
+
+impl Graph {
+    /// Adds a new location to the graph, without any connection, and returns its number
+    pub fn add_location(&mut self) -> u32 {
+        let location = self.nb_locations + 1;
+        self.nb_locations = location;
+        self.regular_connections.push(Vec::new());
+        self.alleyway_connections.push(Vec::new());
+        self.regular_connection_sets.push(LocationSet::new());
+        self.alleyway_connection_sets.push(LocationSet::new());
+        self.crossings.push(Vec::new());
+        self.coordinates.push(None);
+        self.districts.push(None);
+        location
+    }
+
+    /// Adds a connection going from `location` to `destination` only
+    pub fn add_connection(&mut self, location: &u32, destination: &u32, cnx_type: ConnectionType) -> Result<(), Error> {
+        try!(Graph::verify_connections(&[*location, *destination], self.nb_locations));
+        let idx = *location as usize - MIN_INDEX;
+        let (connections, connection_sets) = match cnx_type {
+            ConnectionType::Regular => (&mut self.regular_connections, &mut self.regular_connection_sets),
+            ConnectionType::Alleyway => (&mut self.alleyway_connections, &mut self.alleyway_connection_sets),
+        };
+        if !connections[idx].contains(destination) {
+            connections[idx].push(*destination);
+            connection_sets[idx].insert(destination);
+        }
+        Ok(())
+    }
+
+    /// Adds a connection between two locations, in both directions
+    pub fn add_symmetric_connection(&mut self, location: &u32, destination: &u32, cnx_type: ConnectionType) -> Result<(), Error> {
+        try!(self.add_connection(location, destination, cnx_type));
+        self.add_connection(destination, location, cnx_type)
+    }
+
+    /// Removes the connection going from `location` to `destination`, and returns whether there
+    /// was one
+    pub fn remove_connection(&mut self, location: &u32, destination: &u32, cnx_type: ConnectionType) -> bool {
+        let idx = match Graph::location_index(location) {
+            Some(idx) if idx < self.nb_locations as usize => idx,
+            _ => return false,
+        };
+        let (connections, connection_sets) = match cnx_type {
+            ConnectionType::Regular => (&mut self.regular_connections, &mut self.regular_connection_sets),
+            ConnectionType::Alleyway => (&mut self.alleyway_connections, &mut self.alleyway_connection_sets),
+        };
+        let nb_connections = connections[idx].len();
+        connections[idx].retain(|cnx| cnx != destination);
+        connection_sets[idx].remove(destination);
+        connections[idx].len() != nb_connections
+    }
+
+    /// Removes the connection between two locations, in both directions, and returns whether
+    /// there was one in either direction
+    pub fn remove_symmetric_connection(&mut self, location: &u32, destination: &u32, cnx_type: ConnectionType) -> bool {
+        let removed = self.remove_connection(location, destination, cnx_type);
+        self.remove_connection(destination, location, cnx_type) || removed
+    }
+
+    /// Sets the crossings (where police pawns stand) adjacent to a location
+    pub fn set_crossings_for_location(&mut self, location: &u32, crossings: Vec<u32>) -> Result<(), Error> {
+        try!(Graph::verify_connections(&[*location], self.nb_locations));
+        try!(Graph::verify_crossings(location, &crossings));
+        self.crossings[*location as usize - MIN_INDEX] = crossings;
+        Ok(())
+    }
+
+    pub fn set_coordinates_for_location(&mut self, location: &u32, coordinates: Option<(f64, f64)>) -> Result<(), Error> {
+        try!(Graph::verify_connections(&[*location], self.nb_locations));
+        self.coordinates[*location as usize - MIN_INDEX] = coordinates;
+        Ok(())
+    }
+
+    pub fn set_district_for_location(&mut self, location: &u32, district: Option<String>) -> Result<(), Error> {
+        try!(Graph::verify_connections(&[*location], self.nb_locations));
+        self.districts[*location as usize - MIN_INDEX] = district;
+        Ok(())
+    }
+}
+
+
+/// Builds a `Graph` from Rust code, e.g. to make small boards.
+///
+/// Locations are numbered from 1 in the order they're added, and connections are only checked
+/// when building the graph, the same way `Graph::from_json` checks them.
+#[derive(Debug, Default)]
+pub struct GraphBuilder {
+    nb_locations: u32,
+    connections: Vec<(u32, u32, ConnectionType)>,
+    crossings: BTreeMap<u32, Vec<u32>>,
+    coordinates: BTreeMap<u32, (f64, f64)>,
+    districts: BTreeMap<u32, String>,
+}
+
+impl GraphBuilder {
+    pub fn new() -> GraphBuilder {
+        GraphBuilder::default()
+    }
+
+    /// Creates a builder with locations 1 to `nb_locations` already added
+    pub fn with_locations(nb_locations: u32) -> GraphBuilder {
+        GraphBuilder {
+            nb_locations: nb_locations,
+            ..GraphBuilder::default()
+        }
+    }
+
+    /// Adds a new location, and returns its number
+    pub fn add_location(&mut self) -> u32 {
+        self.nb_locations += 1;
+        self.nb_locations
+    }
+
+    pub fn add_regular_connection(&mut self, location: u32, destination: u32) -> &mut GraphBuilder {
+        self.add_directed_regular_connection(location, destination)
+            .add_directed_regular_connection(destination, location)
+    }
+
+    pub fn add_alleyway_connection(&mut self, location: u32, destination: u32) -> &mut GraphBuilder {
+        self.add_directed_alleyway_connection(location, destination)
+            .add_directed_alleyway_connection(destination, location)
+    }
+
+    /// Adds a regular connection going from `location` to `destination` only
+    pub fn add_directed_regular_connection(&mut self, location: u32, destination: u32) -> &mut GraphBuilder {
+        self.connections.push((location, destination, ConnectionType::Regular));
+        self
+    }
+
+    /// Adds an alleyway connection going from `location` to `destination` only
+    pub fn add_directed_alleyway_connection(&mut self, location: u32, destination: u32) -> &mut GraphBuilder {
+        self.connections.push((location, destination, ConnectionType::Alleyway));
+        self
+    }
+
+    pub fn set_crossings_for_location(&mut self, location: u32, crossings: Vec<u32>) -> &mut GraphBuilder {
+        self.crossings.insert(location, crossings);
+        self
+    }
+
+    pub fn set_coordinates_for_location(&mut self, location: u32, x: f64, y: f64) -> &mut GraphBuilder {
+        self.coordinates.insert(location, (x, y));
+        self
+    }
+
+    pub fn set_district_for_location(&mut self, location: u32, district: &str) -> &mut GraphBuilder {
+        self.districts.insert(location, district.to_string());
+        self
+    }
+
+    pub fn build(&self) -> Result<Graph, Error> {
+        let nb_locations = self.nb_locations as usize;
+        let mut all_regular_connections: Vec<Vec<u32>> = vec![Vec::new(); nb_locations];
+        let mut all_alleyway_connections: Vec<Vec<u32>> = vec![Vec::new(); nb_locations];
+        let mut all_crossings: Vec<Vec<u32>> = vec![Vec::new(); nb_locations];
+
+        for &(location, destination, cnx_type) in &self.connections {
+            try!(Graph::verify_connections(&[location], self.nb_locations));
+            let connections = match cnx_type {
+                ConnectionType::Regular => &mut all_regular_connections[location as usize - MIN_INDEX],
+                ConnectionType::Alleyway => &mut all_alleyway_connections[location as usize - MIN_INDEX],
+            };
+            // Adding the same connection twice (e.g. both ways) isn't a mistake with the builder
+            if !connections.contains(&destination) {
+                connections.push(destination);
+            }
+        }
+        for (location, crossings) in &self.crossings {
+            try!(Graph::verify_connections(&[*location], self.nb_locations));
+            all_crossings[*location as usize - MIN_INDEX] = crossings.clone();
+        }
+
+        let mut graph = try!(Graph::from_connections(all_regular_connections, all_alleyway_connections, all_crossings));
+        for (location, coordinates) in &self.coordinates {
+            try!(graph.set_coordinates_for_location(location, Some(*coordinates)));
+        }
+        for (location, district) in &self.districts {
+            try!(graph.set_district_for_location(location, Some(district.clone())));
         }
+        Ok(graph)
     }
+}
+
+impl Graph {
+    fn connections_for_type(&self, location: &u32, cnx_type: ConnectionType) -> Option<&Vec<u32>> {
+        match cnx_type {
+            ConnectionType::Regular => self.connections_for_location(location),
+            ConnectionType::Alleyway => self.alleyway_connections_for_location(location),
+        }
+    }
+
+    // Number of regular moves between two locations, or `None` if it's more than `max_distance`
+    fn regular_distance_between_locations(&self, location: &u32, destination: &u32, max_distance: u32) -> Option<u32> {
+        let mut visited = LocationSet::from_iter(Some(*location));
+        let mut frontier = visited.clone();
+        for distance in 0..=max_distance {
+            if frontier.contains(destination) {
+                return Some(distance);
+            }
+            frontier = frontier.iter().fold(LocationSet::new(), |mut next, loc| {
+                next.union_with(&self.regular_connection_sets[(loc as usize) - 1]);
+                next
+            }).difference(&visited);
+            visited.union_with(&frontier);
+        }
+        None
+    }
+
+    /// Checks the board data for inconsistencies that can't be caught while parsing it, such as
+    /// one-way or duplicate connections. An empty list means no problem was found.
+    pub fn validate(&self) -> Vec<ValidationFinding> {
+        let mut findings = Vec::new();
+
+        for location in (MIN_INDEX as u32)..=self.nb_locations {
 
-    pub fn all_connections_for_location(&self, location: &u32) -> Option<Vec<&u32>> {
-        if let loc @ 1 ... 195 = *location {
-            let mut all_cnx_set = HashSet::new();
-            if let Some(regular_cnx) = self.regular_connections.get(loc as usize - 1) {
-                for cnx in regular_cnx {
-                    all_cnx_set.insert(cnx);
+            for &cnx_type in &[ConnectionType::Regular, ConnectionType::Alleyway] {
+                // unwrap() because all locations in range have connections
+                let connections = self.connections_for_type(&location, cnx_type).unwrap();
+                for (cnx_idx, connection) in connections.iter().enumerate() {
+                    if *connection == location {
+                        findings.push(ValidationFinding::SelfConnection(location, cnx_type));
+                        continue;
+                    }
+                    // Only report duplicates once, on their first occurrence
+                    if connections[..cnx_idx].contains(connection) {
+                        continue;
+                    }
+                    if connections[(cnx_idx + 1)..].contains(connection) {
+                        findings.push(ValidationFinding::DuplicateConnection(location, *connection, cnx_type));
+                    }
+                    let is_symmetric = self.connections_for_type(connection, cnx_type).is_some_and(|back_cnx| {
+                        back_cnx.contains(&location)
+                    });
+                    if !is_symmetric {
+                        findings.push(ValidationFinding::OneWayConnection(location, *connection, cnx_type));
+                    }
+                    if cnx_type == ConnectionType::Alleyway {
+                        let max_distance = MAX_PLAUSIBLE_ALLEYWAY_DISTANCE;
+                        if self.regular_distance_between_locations(&location, connection, max_distance).is_none() {
+                            let distance = self.regular_distance_between_locations(&location, connection, self.nb_locations);
+                            findings.push(ValidationFinding::ImplausibleAlleyway(location, *connection, distance));
+                        }
+                    }
                 }
             }
-            if let Some(alleyway_cnx) = self.alleyway_connections.get(loc as usize - 1) {
-                for cnx in alleyway_cnx {
-                    all_cnx_set.insert(cnx);
+
+            if self.connections_for_location(&location).is_none_or(|cnx| cnx.iter().all(|c| *c == location)) {
+                findings.push(ValidationFinding::IsolatedLocation(location));
+            }
+
+            if self.has_crossings() {
+                // unwrap() because all locations in range have connections
+                for connection in self.connections_for_location(&location).unwrap() {
+                    // Self connections are reported above, and symmetric moves on their lower end
+                    let is_already_reported = *connection == location || *connection < location &&
+                        self.connections_for_location(connection).is_some_and(|c| c.contains(&location));
+                    if !is_already_reported && self.crossings_between_locations(&location, connection).is_empty() {
+                        findings.push(ValidationFinding::RegularMoveWithoutCrossing(location, *connection));
+                    }
                 }
             }
-            let all_cnx = Vec::from_iter(all_cnx_set.into_iter());
-            return Some(all_cnx);
         }
-        None
+
+        // A crossing is used by regular moves only if it's next to at least two locations
+        let mut locations_per_crossing = BTreeMap::new();
+        for crossings in &self.crossings {
+            for crossing in crossings {
+                *locations_per_crossing.entry(*crossing).or_insert(0) += 1;
+            }
+        }
+        for (crossing, nb_locations) in locations_per_crossing {
+            if nb_locations < 2 {
+                findings.push(ValidationFinding::UnusedCrossing(crossing));
+            }
+        }
+
+        findings
+    }
+
+    /// Exports the graph to the Graphviz DOT format, with regular connections as solid edges and
+    /// alleyway connections as dashed edges. See `to_dot_highlighting` to highlight locations.
+    pub fn to_dot(&self) -> String {
+        self.to_dot_highlighting(&LocationSet::new())
+    }
+
+    /// Same as `to_dot`, with the given locations (e.g. candidate hideouts, or a path) filled in.
+    /// Connections going one way only are drawn with an arrow, so they stand out.
+    pub fn to_dot_highlighting(&self, highlighted: &LocationSet) -> String {
+        let mut dot = String::from("graph lfw {\n    node [shape=circle];\n");
+
+        // Locations with known coordinates are pinned where they are on the board (with `neato`)
+        for location in (MIN_INDEX as u32)..=self.nb_locations {
+            let mut attributes = Vec::new();
+            if let Some((x, y)) = self.coordinates_for_location(&location) {
+                attributes.push(format!("pos=\"{},{}!\"", x, y));
+            }
+            if highlighted.contains(&location) {
+                attributes.push("style=filled, fillcolor=red".to_string());
+            }
+            if !attributes.is_empty() {
+                dot.push_str(&format!("    {} [{}];\n", location, attributes.join(", ")));
+            }
+        }
+
+        for location in (MIN_INDEX as u32)..=self.nb_locations {
+            for &(cnx_type, style) in &[(ConnectionType::Regular, "solid"), (ConnectionType::Alleyway, "dashed")] {
+                let connections = match cnx_type {
+                    ConnectionType::Regular => self.connection_set_for_location(&location),
+                    ConnectionType::Alleyway => self.alleyway_connection_set_for_location(&location),
+                };
+                // unwrap() because all locations in range have connections
+                for connection in connections.unwrap() {
+                    let is_symmetric = self.connections_for_type(&connection, cnx_type).is_some_and(|back_cnx| {
+                        back_cnx.contains(&location)
+                    });
+                    if is_symmetric && connection < location {
+                        // Already written from the other end
+                        continue;
+                    }
+                    let direction = if is_symmetric { "" } else { ", dir=forward" };
+                    dot.push_str(&format!("    {} -- {} [style={}{}];\n", location, connection, style, direction));
+                }
+            }
+        }
+
+        dot.push_str("}\n");
+        dot
     }
 }
 
@@ -162,11 +770,11 @@ impl Index<usize> for Graph {
     type Output = Vec<u32>;
 
     fn index(&self, location: usize) -> &Vec<u32> {
-        match location {
-            MIN_INDEX...MAX_INDEX => &(self.regular_connections[location - 1]),
-            loc => {
+        match self.connections_for_location(&(location as u32)) {
+            Some(connections) => connections,
+            None => {
                 panic!("Invalid location {} (must be between {} and {})",
-                        loc, MIN_INDEX, MAX_INDEX)
+                        location, MIN_INDEX, self.nb_locations)
             }
         }
     }
@@ -0,0 +1,169 @@
+
+use std::fmt;
+
+use lfw::{ConnectionType, Graph, Solver, UnreachableDistance};
+
+
+/// A connection found in only one of the two graphs compared by `GraphDiff`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionChange {
+    /// A connection from a location to another, only in the new graph
+    Added(u32, u32, ConnectionType),
+    /// A connection from a location to another, only in the old graph
+    Removed(u32, u32, ConnectionType),
+}
+
+impl fmt::Display for ConnectionChange {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            ConnectionChange::Added(ref loc, ref cnx, ref cnx_type) => {
+                write!(fmt, "Location {}: added {} connection to {}", loc, cnx_type, cnx)
+            },
+            ConnectionChange::Removed(ref loc, ref cnx, ref cnx_type) => {
+                write!(fmt, "Location {}: removed {} connection to {}", loc, cnx_type, cnx)
+            },
+        }
+    }
+}
+
+
+/// A change in the shortest distance (using any connection) from a location to another.
+/// Distances are `UnreachableDistance` when the destination can't be reached.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DistanceChange {
+    location: u32,
+    destination: u32,
+    old_distance: u32,
+    new_distance: u32,
+}
+
+impl DistanceChange {
+    pub fn location(&self) -> &u32 {
+        &(self.location)
+    }
+
+    pub fn destination(&self) -> &u32 {
+        &(self.destination)
+    }
+
+    pub fn old_distance(&self) -> &u32 {
+        &(self.old_distance)
+    }
+
+    pub fn new_distance(&self) -> &u32 {
+        &(self.new_distance)
+    }
+}
+
+impl fmt::Display for DistanceChange {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        let format_distance = |distance: u32| {
+            if distance == UnreachableDistance { "unreachable".to_string() } else { distance.to_string() }
+        };
+        write!(fmt, "{} to {}: {} -> {}",
+               self.location, self.destination, format_distance(self.old_distance), format_distance(self.new_distance))
+    }
+}
+
+
+/// The differences between two versions of a board
+///
+/// Each connection change comes with the distances it changes on its own, i.e. when applied to
+/// the old graph without any of the other changes.
+#[derive(Debug)]
+pub struct GraphDiff {
+    connection_changes: Vec<(ConnectionChange, Vec<DistanceChange>)>,
+    distance_changes: Vec<DistanceChange>,
+}
+
+
+impl GraphDiff {
+    /// Compares two graphs. Locations that only exist in one of them have all their connections
+    /// reported as added or removed, but their distances aren't compared.
+    pub fn between(old_graph: &Graph, new_graph: &Graph) -> GraphDiff {
+        let mut changes = Vec::new();
+        let last_location = old_graph.nb_locations().max(new_graph.nb_locations());
+        for location in 1..=last_location {
+            for &cnx_type in &[ConnectionType::Regular, ConnectionType::Alleyway] {
+                let old_cnx = GraphDiff::connections_for_type(old_graph, &location, cnx_type);
+                let new_cnx = GraphDiff::connections_for_type(new_graph, &location, cnx_type);
+                for cnx in new_cnx.iter().filter(|cnx| !old_cnx.contains(cnx)) {
+                    changes.push(ConnectionChange::Added(location, *cnx, cnx_type));
+                }
+                for cnx in old_cnx.iter().filter(|cnx| !new_cnx.contains(cnx)) {
+                    changes.push(ConnectionChange::Removed(location, *cnx, cnx_type));
+                }
+            }
+        }
+
+        let old_distances = Solver::precompute_shortest_distances(old_graph);
+        let connection_changes = changes.into_iter().map(|change| {
+            let distance_changes = match GraphDiff::apply_change(old_graph, &change) {
+                Some(graph) => GraphDiff::compare_distances(&old_distances, &Solver::precompute_shortest_distances(&graph)),
+                // Changes involving locations that didn't exist can't be applied on their own
+                None => Vec::new(),
+            };
+            (change, distance_changes)
+        }).collect();
+
+        GraphDiff {
+            connection_changes: connection_changes,
+            distance_changes: GraphDiff::compare_distances(&old_distances, &Solver::precompute_shortest_distances(new_graph)),
+        }
+    }
+
+    fn connections_for_type(graph: &Graph, location: &u32, cnx_type: ConnectionType) -> Vec<u32> {
+        let connections = match cnx_type {
+            ConnectionType::Regular => graph.connection_set_for_location(location),
+            ConnectionType::Alleyway => graph.alleyway_connection_set_for_location(location),
+        };
+        connections.map_or(Vec::new(), |cnx| cnx.iter().collect())
+    }
+
+    fn apply_change(graph: &Graph, change: &ConnectionChange) -> Option<Graph> {
+        let mut changed_graph = graph.clone();
+        match *change {
+            ConnectionChange::Added(ref loc, ref cnx, cnx_type) => {
+                if changed_graph.add_connection(loc, cnx, cnx_type).is_err() {
+                    return None;
+                }
+            },
+            ConnectionChange::Removed(ref loc, ref cnx, cnx_type) => {
+                changed_graph.remove_connection(loc, cnx, cnx_type);
+            },
+        }
+        Some(changed_graph)
+    }
+
+    // Compares the distances between the locations found in both tables
+    fn compare_distances(old_distances: &[Vec<u32>], new_distances: &[Vec<u32>]) -> Vec<DistanceChange> {
+        let mut changes = Vec::new();
+        for (loc_idx, (old_row, new_row)) in old_distances.iter().zip(new_distances.iter()).enumerate() {
+            for (dest_idx, (old_distance, new_distance)) in old_row.iter().zip(new_row.iter()).enumerate() {
+                if old_distance != new_distance {
+                    changes.push(DistanceChange {
+                        location: loc_idx as u32 + 1,
+                        destination: dest_idx as u32 + 1,
+                        old_distance: *old_distance,
+                        new_distance: *new_distance,
+                    });
+                }
+            }
+        }
+        changes
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.connection_changes.is_empty()
+    }
+
+    /// Returns the connections added or removed, by location, each with the distances it changes
+    pub fn connection_changes(&self) -> &Vec<(ConnectionChange, Vec<DistanceChange>)> {
+        &(self.connection_changes)
+    }
+
+    /// Returns all the distances changed between the two graphs
+    pub fn distance_changes(&self) -> &Vec<DistanceChange> {
+        &(self.distance_changes)
+    }
+}
@@ -0,0 +1,271 @@
+
+use std::cmp;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::{BitAnd, BitOr, Sub};
+
+
+const WORD_SIZE: usize = 64;
+
+
+/// A set of locations, stored as a bitset where bit N is set if location N is in it.
+///
+/// It only has as many words as needed for its highest location, so a set for the standard
+/// board is a handful of words, and operations on it are much cheaper than on `Vec`s or
+/// `HashSet`s of single locations. There is no limit on the locations it can hold.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct LocationSet {
+    words: Vec<u64>,
+}
+
+
+impl LocationSet {
+    pub fn new() -> LocationSet {
+        LocationSet::default()
+    }
+
+    /// Returns a set with all locations from 1 to `last_location` (included)
+    pub fn with_all_locations(last_location: u32) -> LocationSet {
+        LocationSet::from_iter(1..=last_location)
+    }
+
+    fn word_and_mask(location: &u32) -> (usize, u64) {
+        let idx = *location as usize;
+        (idx / WORD_SIZE, 1 << (idx % WORD_SIZE))
+    }
+
+    // Words up to the last one with a location, so sets only differing by trailing empty words
+    // are equal
+    fn significant_words(&self) -> &[u64] {
+        let len = self.words.iter().rposition(|word| *word != 0).map_or(0, |idx| idx + 1);
+        &self.words[..len]
+    }
+
+    pub fn insert(&mut self, location: &u32) {
+        let (word, mask) = LocationSet::word_and_mask(location);
+        if word >= self.words.len() {
+            self.words.resize(word + 1, 0);
+        }
+        self.words[word] |= mask;
+    }
+
+    pub fn remove(&mut self, location: &u32) {
+        let (word, mask) = LocationSet::word_and_mask(location);
+        if let Some(word) = self.words.get_mut(word) {
+            *word &= !mask;
+        }
+    }
+
+    pub fn contains(&self, location: &u32) -> bool {
+        let (word, mask) = LocationSet::word_and_mask(location);
+        self.words.get(word).is_some_and(|word| word & mask != 0)
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|word| word.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|word| *word == 0)
+    }
+
+    pub fn union(&self, other: &LocationSet) -> LocationSet {
+        let mut set = self.clone();
+        set.union_with(other);
+        set
+    }
+
+    /// Adds all the locations of `other` to the set, without allocating a new one
+    pub fn union_with(&mut self, other: &LocationSet) {
+        if other.words.len() > self.words.len() {
+            self.words.resize(other.words.len(), 0);
+        }
+        for (word, other_word) in self.words.iter_mut().zip(other.words.iter()) {
+            *word |= *other_word;
+        }
+    }
+
+    pub fn intersection(&self, other: &LocationSet) -> LocationSet {
+        let len = cmp::min(self.words.len(), other.words.len());
+        let mut set = LocationSet { words: self.words[..len].to_vec() };
+        set.intersect_with(other);
+        set
+    }
+
+    /// Only keeps the locations that are also in `other`, without allocating a new set
+    pub fn intersect_with(&mut self, other: &LocationSet) {
+        self.words.truncate(other.words.len());
+        for (word, other_word) in self.words.iter_mut().zip(other.words.iter()) {
+            *word &= *other_word;
+        }
+    }
+
+    pub fn difference(&self, other: &LocationSet) -> LocationSet {
+        let mut words = self.words.clone();
+        for (word, other_word) in words.iter_mut().zip(other.words.iter()) {
+            *word &= !*other_word;
+        }
+        LocationSet { words: words }
+    }
+
+    pub fn is_disjoint(&self, other: &LocationSet) -> bool {
+        self.words.iter().zip(other.words.iter()).all(|(word, other_word)| word & other_word == 0)
+    }
+
+    /// Iterates over the locations of the set, in ascending order
+    pub fn iter(&self) -> Iter<'_> {
+        Iter {
+            words: &self.words,
+            word_idx: 0,
+            word: self.words.first().cloned().unwrap_or(0),
+        }
+    }
+}
+
+
+impl PartialEq for LocationSet {
+    fn eq(&self, other: &LocationSet) -> bool {
+        self.significant_words() == other.significant_words()
+    }
+}
+
+
+impl Eq for LocationSet {}
+
+
+impl Hash for LocationSet {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.significant_words().hash(state)
+    }
+}
+
+
+impl fmt::Debug for LocationSet {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_set().entries(self.iter()).finish()
+    }
+}
+
+
+impl FromIterator<u32> for LocationSet {
+    fn from_iter<I: IntoIterator<Item = u32>>(iter: I) -> LocationSet {
+        let mut set = LocationSet::new();
+        for location in iter {
+            set.insert(&location);
+        }
+        set
+    }
+}
+
+
+impl<'a> FromIterator<&'a u32> for LocationSet {
+    fn from_iter<I: IntoIterator<Item = &'a u32>>(iter: I) -> LocationSet {
+        LocationSet::from_iter(iter.into_iter().cloned())
+    }
+}
+
+
+impl IntoIterator for LocationSet {
+    type Item = u32;
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> IntoIter {
+        let word = self.words.first().cloned().unwrap_or(0);
+        IntoIter {
+            words: self.words,
+            word_idx: 0,
+            word: word,
+        }
+    }
+}
+
+
+impl<'a> IntoIterator for &'a LocationSet {
+    type Item = u32;
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Iter<'a> {
+        self.iter()
+    }
+}
+
+
+impl BitOr for LocationSet {
+    type Output = LocationSet;
+
+    fn bitor(self, other: LocationSet) -> LocationSet {
+        self.union(&other)
+    }
+}
+
+
+impl BitAnd for LocationSet {
+    type Output = LocationSet;
+
+    fn bitand(self, other: LocationSet) -> LocationSet {
+        self.intersection(&other)
+    }
+}
+
+
+impl Sub for LocationSet {
+    type Output = LocationSet;
+
+    fn sub(self, other: LocationSet) -> LocationSet {
+        self.difference(&other)
+    }
+}
+
+
+/// Iterator over the locations of a `LocationSet`, see `LocationSet::iter`.
+#[derive(Debug)]
+pub struct Iter<'a> {
+    words: &'a [u64],
+    word_idx: usize,
+    // Locations of the current word that haven't been returned yet
+    word: u64,
+}
+
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        next_location(self.words, &mut self.word_idx, &mut self.word)
+    }
+}
+
+
+/// Iterator over the locations of a `LocationSet` it owns, see `LocationSet::into_iter`.
+#[derive(Debug)]
+pub struct IntoIter {
+    words: Vec<u64>,
+    word_idx: usize,
+    word: u64,
+}
+
+
+impl Iterator for IntoIter {
+    type Item = u32;
+
+    fn next(&mut self) -> Option<u32> {
+        next_location(&self.words, &mut self.word_idx, &mut self.word)
+    }
+}
+
+
+// Returns the lowest location left in `word`, moving on to the following words once it's empty
+fn next_location(words: &[u64], word_idx: &mut usize, word: &mut u64) -> Option<u32> {
+    while *word_idx < words.len() {
+        if *word != 0 {
+            let bit = word.trailing_zeros() as usize;
+            // Clear the lowest bit set, so the next call finds the following location
+            *word &= *word - 1;
+            return Some((*word_idx * WORD_SIZE + bit) as u32);
+        }
+        *word_idx += 1;
+        *word = words.get(*word_idx).cloned().unwrap_or(0);
+    }
+    None
+}
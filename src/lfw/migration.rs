@@ -0,0 +1,68 @@
+
+extern crate serde_json;
+use self::serde_json::Map as JsonMap;
+use self::serde_json::Value as JsonValue;
+use self::serde_json::from_value as parse_json_value;
+
+use lfw::night::Error;
+
+
+/// Version of the game data layout written by `Night::to_json`
+///
+/// 1. The original layout, with `jack_known_locations` (locations Jack went through at some point
+///    during the night) instead of clues. It had no `version` key.
+/// 2. Clues found on a given turn (`jack_clues`), carriages, police and arrests.
+pub const CURRENT_VERSION: u32 = 2;
+pub const VERSION_KEY: &'static str = "version";
+
+const LEGACY_KNOWN_LOCATIONS_KEY: &'static str = "jack_known_locations";
+
+
+/// Returns the version of a night's game data. Files written before versioning have no
+/// `version` key, and are told apart by the keys they use.
+pub fn night_json_version(json: &JsonValue) -> Result<u32, Error> {
+    match json.get(VERSION_KEY) {
+        Some(version) => parse_json_value(version.clone()).map_err(Error::InvalidJSONValue),
+        None if json.get(LEGACY_KNOWN_LOCATIONS_KEY).is_some() => Ok(1),
+        None => Ok(CURRENT_VERSION),
+    }
+}
+
+
+/// Upgrades a night's game data, in any of the layouts it had over time, to the current one
+pub fn upgrade_night_json(mut json: JsonValue) -> Result<JsonValue, Error> {
+    let version = try!(night_json_version(&json));
+    if !(1..=CURRENT_VERSION).contains(&version) {
+        return Err(Error::UnsupportedVersion(version));
+    }
+
+    if version < 2 {
+        json = try!(upgrade_from_known_locations(json));
+    }
+
+    if let Some(night_object) = json.as_object_mut() {
+        night_object.insert(VERSION_KEY.to_string(), JsonValue::from(CURRENT_VERSION));
+    }
+    Ok(json)
+}
+
+
+// Known locations didn't say when Jack went through them, so they become clues found on the last
+// turn, the latest one clues can be found on: Jack could have left them anywhere before his last
+// move, which takes him to his hideout.
+fn upgrade_from_known_locations(mut json: JsonValue) -> Result<JsonValue, Error> {
+    let nb_moves = json.get("jack_nb_moves").and_then(|nb| nb.as_u64()).unwrap_or(0);
+    if let Some(night_object) = json.as_object_mut() {
+        if let Some(known_locations) = night_object.remove(LEGACY_KNOWN_LOCATIONS_KEY) {
+            let known_locations = try!(parse_json_value::<Vec<u32>>(known_locations).map_err(Error::InvalidJSONValue));
+            let clues = known_locations.iter().map(|location| {
+                let mut clue = JsonMap::new();
+                clue.insert("location".to_string(), JsonValue::from(*location));
+                clue.insert("found_on_turn".to_string(), JsonValue::from(nb_moves));
+                JsonValue::Object(clue)
+            }).collect();
+            night_object.insert("jack_clues".to_string(), JsonValue::Array(clues));
+        }
+    }
+    Ok(json)
+}
@@ -2,8 +2,21 @@
 mod solver;
 mod graph;
 mod night;
+mod migration;
+mod distribution;
+mod location_set;
+mod graph_diff;
+mod cache;
+mod formats;
 
-pub use self::solver::Solver;
-pub use self::graph::Graph;
-pub use self::graph::{Error as GraphError, MIN_INDEX as GraphMinIndex, MAX_INDEX as GraphMaxIndex};
-pub use self::night::Night;
+pub use self::solver::{Solver, Path, Paths, UNREACHABLE as UnreachableDistance};
+pub use self::graph::{Graph, GraphBuilder};
+pub use self::graph::{Error as GraphError, MIN_INDEX as GraphMinIndex, STANDARD_GRAPH_JSON};
+pub use self::graph::{ConnectionType, ValidationFinding};
+pub use self::night::{Night, ClueObservation, ArrestAttempt, ArrestOutcome, Error as NightError, EVENTS_KEY as NightEventsKey, MAX_NB_MOVES as NightMaxNbMoves};
+pub use self::migration::{upgrade_night_json, night_json_version, CURRENT_VERSION as GameDataVersion};
+pub use self::distribution::HideoutDistribution;
+pub use self::graph_diff::{GraphDiff, ConnectionChange, DistanceChange};
+pub use self::cache::{SolverCache, Error as CacheError};
+pub use self::formats::{DataFormat, Error as DataFormatError};
+pub use self::location_set::{LocationSet, Iter as LocationSetIter, IntoIter as LocationSetIntoIter};
@@ -8,22 +8,52 @@ use self::serde_json::from_value as parse_json_value;
 
 use std::error;
 use std::fmt;
+use std::io::Write;
+use std::iter::FromIterator;
+
+use lfw::{DataFormat, DataFormatError, GameDataVersion, LocationSet};
+use lfw::migration;
 
 #[derive(Debug)]
 pub enum Error {
     InvalidJSONValue(JsonError),
+    UnableToEncode(JsonError),
+    NotParsable(DataFormatError),
+    /// A location that isn't on the board, in the given JSON field
+    LocationOutOfRange(String, u32, u32),
+    /// The murder location is listed as a failed clue, in the given JSON field
+    MurderLocationIsFailedClue(String),
+    /// Game data written by a newer version, which can't be loaded
+    UnsupportedVersion(u32),
+    /// A move Jack didn't make (moves being numbered from 1), in the given JSON field
+    TurnOutOfRange(String, u32, u32),
+    /// More moves than a night can have, see `MAX_NB_MOVES`
+    NbMovesOutOfRange(u32),
+    /// A move listed as both an alleyway and a carriage, in the given JSON field
+    ConflictingMoveTypes(String, u32),
 }
 
 impl error::Error for Error {
     fn description(&self) -> &str {
         match *self {
             Error::InvalidJSONValue(ref error) => error.description(),
+            Error::UnableToEncode(ref error) => error.description(),
+            Error::NotParsable(ref error) => error.description(),
+            Error::LocationOutOfRange(_, _, _) => "location out of range",
+            Error::MurderLocationIsFailedClue(_) => "murder location is a failed clue",
+            Error::TurnOutOfRange(_, _, _) => "turn out of range",
+            Error::UnsupportedVersion(_) => "unsupported game data version",
+            Error::NbMovesOutOfRange(_) => "number of moves out of range",
+            Error::ConflictingMoveTypes(_, _) => "move is both an alleyway and a carriage",
         }
     }
 
     fn cause(&self) -> Option<&error::Error> {
         match *self {
             Error::InvalidJSONValue(ref error) => Some(error),
+            Error::UnableToEncode(ref error) => Some(error),
+            Error::NotParsable(ref error) => Some(error),
+            _ => None,
         }
     }
 }
@@ -32,18 +62,84 @@ impl fmt::Display for Error {
         match *self {
             Error::InvalidJSONValue(ref error) => {
                 write!(fmt, "Unable to decode JSON object: {}", error)
-            }
+            },
+            Error::UnableToEncode(ref error) => {
+                write!(fmt, "Unable to encode night to JSON: {}", error)
+            },
+            Error::NotParsable(ref error) => {
+                write!(fmt, "Night not parsable: {}", error)
+            },
+            Error::LocationOutOfRange(ref field, ref loc, ref nb_locations) => {
+                write!(fmt, "Invalid location {} in {} (must be 1...{})", loc, field, nb_locations)
+            },
+            Error::MurderLocationIsFailedClue(ref field) => {
+                write!(fmt, "Murder location is listed as a failed clue in {}", field)
+            },
+            Error::UnsupportedVersion(ref version) => {
+                write!(fmt, "Game data version {} is not supported (must be 1...{})", version, GameDataVersion)
+            },
+            Error::TurnOutOfRange(ref field, ref turn, ref nb_moves) => {
+                write!(fmt, "Invalid move {} in {} (Jack's moves are 1...{})", turn, field, nb_moves)
+            },
+            Error::NbMovesOutOfRange(ref nb_moves) => {
+                write!(fmt, "Invalid number of moves {} in jack_nb_moves (must be 0...{})", nb_moves, MAX_NB_MOVES)
+            },
+            Error::ConflictingMoveTypes(ref field, ref turn) => {
+                write!(fmt, "Move {} in {} is listed as both an alleyway and a carriage", turn, field)
+            },
         }
     }
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
-pub enum Location {
-    Unknown,
-    NeverVisitedAsOf(u32),
-    VisitedAfter(u32),
-    Visited,
+/// A clue found by the police: Jack passed on `location` at some turn strictly before
+/// `found_on_turn` (turn 0 being the murder location).
+#[derive(Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct ClueObservation {
+    location: u32,
+    found_on_turn: u32,
+}
+
+
+impl ClueObservation {
+    pub fn location(&self) -> &u32 {
+        &(self.location)
+    }
+
+    pub fn found_on_turn(&self) -> &u32 {
+        &(self.found_on_turn)
+    }
+}
+
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ArrestOutcome {
+    Failed,
+    Succeeded,
+}
+
+
+/// An arrest attempted by the police on `location`, right after Jack made the move for `turn`.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ArrestAttempt {
+    location: u32,
+    turn: u32,
+    outcome: ArrestOutcome,
+}
+
+
+impl ArrestAttempt {
+    pub fn location(&self) -> &u32 {
+        &(self.location)
+    }
+
+    pub fn turn(&self) -> &u32 {
+        &(self.turn)
+    }
+
+    pub fn outcome(&self) -> &ArrestOutcome {
+        &(self.outcome)
+    }
 }
 
 
@@ -51,54 +147,310 @@ pub enum Location {
 pub enum MoveType {
     Regular,
     Alleyway,
+    Carriage,
 }
 
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
 pub struct Night {
     murder_location: u32,
     jack_nb_moves: u32,
-    jack_known_locations: Vec<u32>,
+    jack_clues: Vec<ClueObservation>,
     jack_alleyways: Vec<u32>,
+    #[serde(default)]
+    jack_carriages: Vec<u32>,
     failed_clues_per_turn: Vec<Vec<usize>>,
-    #[serde(skip_serializing, default)]
-    locations: Vec<Location>,
+    #[serde(default)]
+    police_per_turn: Vec<Vec<u32>>,
+    #[serde(default)]
+    arrests: Vec<ArrestAttempt>,
+    #[serde(skip)]
+    possible_locations_per_turn: Vec<LocationSet>,
 }
 
 
+/// Something that happened during a night, as written down while playing. Turns are numbered the
+/// same way as in the aggregated format: turn N is Jack's Nth move and the police turn after it.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+enum NightEvent {
+    /// The police searched these locations for clues, and found nothing
+    Searched { turn: u32, locations: Vec<u32> },
+    /// The police found a clue on this location
+    Clue { turn: u32, location: u32 },
+    Alleyway { turn: u32 },
+    Carriage { turn: u32 },
+    /// The police stood on these crossings while Jack moved
+    Police { turn: u32, crossings: Vec<u32> },
+    Arrest { turn: u32, location: u32, outcome: ArrestOutcome },
+}
+
+
+/// A night written down as an ordered list of events, see `Night::from_event_log_json`
+#[derive(Debug, Deserialize)]
+struct NightEventLog {
+    murder_location: u32,
+    jack_nb_moves: u32,
+    events: Vec<NightEvent>,
+}
+
+
+pub const EVENTS_KEY: &'static str = "events";
+
+/// Jack's moves for a night, well above what a night of the game can last, so that a typo in
+/// `jack_nb_moves` is reported instead of having the solver go through billions of turns.
+pub const MAX_NB_MOVES: u32 = 1000;
+
+
 impl Night {
-    pub fn from_json(json: JsonValue) -> Result<Night, Error> {
-        let mut night = try!(parse_json_value::<Night>(json.clone()).or_else(|e| {
-            Err(Error::InvalidJSONValue(e))
-        }));
+    /// Loads a night from its JSON representation, either the aggregated one (with
+    /// `failed_clues_per_turn`, `jack_alleyways`...), in any of its versions, or an event log
+    /// (with `events`)
+    ///
+    /// @param json The night's game data
+    /// @param nb_locations The number of locations of the board the night was played on
+    pub fn from_json(json: JsonValue, nb_locations: u32) -> Result<Night, Error> {
+        if json.get(EVENTS_KEY).is_some() {
+            return Night::from_event_log_json(json, nb_locations);
+        }
+
+        let json = try!(migration::upgrade_night_json(json));
+        let night = try!(parse_json_value::<Night>(json).map_err(Error::InvalidJSONValue));
+        Night::prepared(night, nb_locations)
+    }
+
+    /// Loads a night written in TOML, with the same keys as in JSON (in either format)
+    pub fn from_toml(source: &str, nb_locations: u32) -> Result<Night, Error> {
+        Night::from_json(try!(DataFormat::Toml.parse(source).map_err(Error::NotParsable)), nb_locations)
+    }
+
+    /// Loads a night written in YAML, with the same keys as in JSON (in either format)
+    pub fn from_yaml(source: &str, nb_locations: u32) -> Result<Night, Error> {
+        Night::from_json(try!(DataFormat::Yaml.parse(source).map_err(Error::NotParsable)), nb_locations)
+    }
+
+    /// Loads a night from an event log, e.g.:
+    ///
+    /// ```json
+    /// { "murder_location": 9, "jack_nb_moves": 9, "events": [
+    ///     { "event": "searched", "turn": 3, "locations": [80, 82] },
+    ///     { "event": "clue", "turn": 4, "location": 28 },
+    ///     { "event": "alleyway", "turn": 8 }
+    /// ] }
+    /// ```
+    ///
+    /// Events can also be `carriage` (with a `turn`), `police` (with a `turn` and `crossings`)
+    /// and `arrest` (with a `turn`, `location` and `outcome`, `Failed` or `Succeeded`).
+    pub fn from_event_log_json(json: JsonValue, nb_locations: u32) -> Result<Night, Error> {
+        let event_log = try!(parse_json_value::<NightEventLog>(json).map_err(Error::InvalidJSONValue));
+        let nb_moves = event_log.jack_nb_moves;
+        try!(Night::check_nb_moves(nb_moves));
+
+        let mut night = Night {
+            murder_location: event_log.murder_location,
+            jack_nb_moves: nb_moves,
+            jack_clues: Vec::new(),
+            jack_alleyways: Vec::new(),
+            jack_carriages: Vec::new(),
+            failed_clues_per_turn: Vec::new(),
+            police_per_turn: Vec::new(),
+            arrests: Vec::new(),
+            possible_locations_per_turn: Vec::new(),
+        };
+
+        for (idx, event) in event_log.events.into_iter().enumerate() {
+            let turn = match event {
+                NightEvent::Searched { turn, .. } | NightEvent::Clue { turn, .. } |
+                NightEvent::Alleyway { turn } | NightEvent::Carriage { turn } |
+                NightEvent::Police { turn, .. } | NightEvent::Arrest { turn, .. } => turn,
+            };
+            if turn < 1 || turn > nb_moves {
+                return Err(Error::TurnOutOfRange(format!("events[{}].turn", idx), turn, nb_moves));
+            }
+            let zero_idx_turn = (turn - 1) as usize;
+
+            match event {
+                NightEvent::Searched { locations, .. } => {
+                    for (loc_idx, location) in locations.iter().enumerate() {
+                        let field = format!("events[{}].locations[{}]", idx, loc_idx);
+                        if *location < 1 || *location > nb_locations {
+                            return Err(Error::LocationOutOfRange(field, *location, nb_locations));
+                        }
+                        if *location == night.murder_location {
+                            return Err(Error::MurderLocationIsFailedClue(field));
+                        }
+                    }
+                    if night.failed_clues_per_turn.len() <= zero_idx_turn {
+                        night.failed_clues_per_turn.resize(zero_idx_turn + 1, Vec::new());
+                    }
+                    night.failed_clues_per_turn[zero_idx_turn].extend(locations.iter().map(|l| *l as usize));
+                },
+                NightEvent::Clue { location, .. } => {
+                    if location < 1 || location > nb_locations {
+                        return Err(Error::LocationOutOfRange(format!("events[{}].location", idx), location, nb_locations));
+                    }
+                    night.jack_clues.push(ClueObservation {
+                        location: location,
+                        found_on_turn: turn,
+                    });
+                },
+                // Alleyways and carriages are listed by move index, the first move being 0
+                NightEvent::Alleyway { .. } => {
+                    if night.jack_carriages.contains(&(turn - 1)) {
+                        return Err(Error::ConflictingMoveTypes(format!("events[{}].turn", idx), turn));
+                    }
+                    night.jack_alleyways.push(turn - 1);
+                },
+                NightEvent::Carriage { .. } => {
+                    if night.jack_alleyways.contains(&(turn - 1)) {
+                        return Err(Error::ConflictingMoveTypes(format!("events[{}].turn", idx), turn));
+                    }
+                    night.jack_carriages.push(turn - 1);
+                },
+                NightEvent::Police { crossings, .. } => {
+                    if night.police_per_turn.len() <= zero_idx_turn {
+                        night.police_per_turn.resize(zero_idx_turn + 1, Vec::new());
+                    }
+                    night.police_per_turn[zero_idx_turn].extend(crossings);
+                },
+                NightEvent::Arrest { location, outcome, .. } => {
+                    if location < 1 || location > nb_locations {
+                        return Err(Error::LocationOutOfRange(format!("events[{}].location", idx), location, nb_locations));
+                    }
+                    night.arrests.push(ArrestAttempt {
+                        location: location,
+                        turn: turn,
+                        outcome: outcome,
+                    });
+                },
+            }
+        }
 
-        let mut locations = Vec::<Location>::with_capacity(195);
-        for _ in 0..195 {
-            locations.push(Location::Unknown);
+        Night::prepared(night, nb_locations)
+    }
+
+    /// Returns the night in the current version of the aggregated JSON format, which `from_json`
+    /// loads back the same. Event logs are written in the aggregated format too.
+    pub fn to_json(&self) -> Result<JsonValue, Error> {
+        let mut json = try!(serde_json::to_value(self).map_err(Error::UnableToEncode));
+        if let Some(night_object) = json.as_object_mut() {
+            night_object.insert(migration::VERSION_KEY.to_string(), JsonValue::from(GameDataVersion));
         }
+        Ok(json)
+    }
+
+    /// Same as `to_json`, writing the JSON out (pretty-printed, as it's meant to be edited by hand)
+    pub fn to_writer<W: Write>(&self, writer: W) -> Result<(), Error> {
+        let json = try!(self.to_json());
+        serde_json::to_writer_pretty(writer, &json).map_err(Error::UnableToEncode)
+    }
+
+    // Validates a night freshly loaded, and works out where Jack could be on each turn
+    fn prepared(mut night: Night, nb_locations: u32) -> Result<Night, Error> {
+        try!(night.validate(nb_locations));
 
-        for (zero_idx_turn, failed_clues_for_turn) in (&night.failed_clues_per_turn).iter().enumerate() {
-            let turn = zero_idx_turn + 1; // We don't want turns to be zero indexed
-            for failed_clue in failed_clues_for_turn {
-                locations[failed_clue - 1] = Location::NeverVisitedAsOf(turn as u32)
+        let mut possible_locations = vec![LocationSet::with_all_locations(nb_locations); night.jack_nb_moves as usize];
+
+        // A failed clue means Jack never went on that location up to (and including) that turn
+        for (zero_idx_turn, failed_clues_for_turn) in night.failed_clues_per_turn.iter().enumerate() {
+            let failed_clues = LocationSet::from_iter(failed_clues_for_turn.iter().map(|clue| *clue as u32));
+            for possible_locations_for_turn in possible_locations.iter_mut().take(zero_idx_turn + 1) {
+                *possible_locations_for_turn = possible_locations_for_turn.difference(&failed_clues);
             }
         }
-        for location in &(night.jack_known_locations) {
-            let zero_idx_loc = location - 1; // Night locations are not 0-indexed, but our array is
-            locations[zero_idx_loc as usize] = match locations[zero_idx_loc as usize] {
-                // There's no reason for a location to already be ::VisitedAfter, but never know...
-                Location::NeverVisitedAsOf(turn) | Location::VisitedAfter(turn) => {
-                    Location::VisitedAfter(turn)
-                },
-                _ => Location::Visited,
-            };
+
+        // Whereas arrests only tell us about Jack's location right after the move for their turn
+        for arrest in &night.arrests {
+            if let Some(possible_locations_for_turn) = possible_locations.get_mut((arrest.turn as usize).wrapping_sub(1)) {
+                match arrest.outcome {
+                    ArrestOutcome::Failed => possible_locations_for_turn.remove(&arrest.location),
+                    ArrestOutcome::Succeeded => {
+                        let arrest_location = LocationSet::from_iter(Some(arrest.location));
+                        *possible_locations_for_turn = possible_locations_for_turn.intersection(&arrest_location);
+                    }
+                }
+            }
         }
 
-        night.locations = locations;
+        night.possible_locations_per_turn = possible_locations;
 
         Ok(night)
     }
 
+    fn check_nb_moves(nb_moves: u32) -> Result<(), Error> {
+        if nb_moves <= MAX_NB_MOVES {
+            Ok(())
+        } else {
+            Err(Error::NbMovesOutOfRange(nb_moves))
+        }
+    }
+
+    // Checks that the night makes sense on a board with `nb_locations` locations, so that errors
+    // in the game data are reported with the field they're in rather than going unnoticed.
+    fn validate(&self, nb_locations: u32) -> Result<(), Error> {
+        // Checked first, as the other checks (and preparing the night) go through each move
+        try!(Night::check_nb_moves(self.jack_nb_moves));
+
+        let check_location = |field: String, location: u32| {
+            if location >= 1 && location <= nb_locations {
+                Ok(())
+            } else {
+                Err(Error::LocationOutOfRange(field, location, nb_locations))
+            }
+        };
+        let check_turn = |field: String, turn: u32| {
+            if turn >= 1 && turn <= self.jack_nb_moves {
+                Ok(())
+            } else {
+                Err(Error::TurnOutOfRange(field, turn, self.jack_nb_moves))
+            }
+        };
+
+        try!(check_location("murder_location".to_string(), self.murder_location));
+
+        for (idx, clue) in self.jack_clues.iter().enumerate() {
+            try!(check_location(format!("jack_clues[{}].location", idx), clue.location));
+            try!(check_turn(format!("jack_clues[{}].found_on_turn", idx), clue.found_on_turn));
+        }
+
+        // Alleyways and carriages are listed by move index, the first move being 0
+        for (idx, turn) in self.jack_alleyways.iter().enumerate() {
+            try!(check_turn(format!("jack_alleyways[{}]", idx), turn.saturating_add(1)));
+        }
+        for (idx, turn) in self.jack_carriages.iter().enumerate() {
+            let field = format!("jack_carriages[{}]", idx);
+            try!(check_turn(field.clone(), turn.saturating_add(1)));
+            if self.jack_alleyways.contains(turn) {
+                return Err(Error::ConflictingMoveTypes(field, *turn));
+            }
+        }
+
+        for (zero_idx_turn, failed_clues_for_turn) in self.failed_clues_per_turn.iter().enumerate() {
+            let field = format!("failed_clues_per_turn[{}]", zero_idx_turn);
+            try!(check_turn(field.clone(), zero_idx_turn as u32 + 1));
+            for (idx, clue) in failed_clues_for_turn.iter().enumerate() {
+                let clue_field = format!("{}[{}]", field, idx);
+                // Values too big for a location are reported as the biggest one
+                let clue_location = if *clue > u32::MAX as usize { u32::MAX } else { *clue as u32 };
+                try!(check_location(clue_field.clone(), clue_location));
+                if clue_location == self.murder_location {
+                    return Err(Error::MurderLocationIsFailedClue(clue_field));
+                }
+            }
+        }
+
+        for zero_idx_turn in 0..self.police_per_turn.len() {
+            try!(check_turn(format!("police_per_turn[{}]", zero_idx_turn), zero_idx_turn as u32 + 1));
+        }
+
+        for (idx, arrest) in self.arrests.iter().enumerate() {
+            try!(check_location(format!("arrests[{}].location", idx), arrest.location));
+            try!(check_turn(format!("arrests[{}].turn", idx), arrest.turn));
+        }
+
+        Ok(())
+    }
+
     pub fn murder_location(&self) -> &u32 {
         &(self.murder_location)
     }
@@ -111,17 +463,37 @@ impl Night {
         &(self.jack_alleyways)
     }
 
-    pub fn jack_known_locations(&self) -> &Vec<u32> {
-        &(self.jack_known_locations)
+    pub fn jack_carriages(&self) -> &Vec<u32> {
+        &(self.jack_carriages)
+    }
+
+    pub fn jack_clues(&self) -> &Vec<ClueObservation> {
+        &(self.jack_clues)
+    }
+
+    /// Returns the crossings occupied by the police while Jack makes the move for `turn`
+    pub fn police_crossings_for_turn(&self, turn: &u32) -> &[u32] {
+        match self.police_per_turn.get((turn - 1) as usize) {
+            Some(crossings) => crossings,
+            None => &[],
+        }
+    }
+
+    pub fn arrests(&self) -> &Vec<ArrestAttempt> {
+        &(self.arrests)
     }
 
-    pub fn jack_visit_status_for_location(&self, location_index: &u32) -> &Location {
-        &(self.locations[(location_index - 1) as usize])
+    /// Returns the locations Jack could be on right after making the move for `turn`, given the
+    /// failed clues and arrests of the night, or `None` for turns outside of the night.
+    pub fn jack_possible_locations_for_turn(&self, turn: &u32) -> Option<&LocationSet> {
+        self.possible_locations_per_turn.get((*turn as usize).wrapping_sub(1))
     }
 
     pub fn jack_move_type_for_turn(&self, turn: &u32) -> MoveType {
         if self.jack_alleyways().contains(&(turn - 1)) {
             MoveType::Alleyway
+        } else if self.jack_carriages().contains(&(turn - 1)) {
+            MoveType::Carriage
         } else {
             MoveType::Regular
         }
@@ -1,11 +1,27 @@
 
 use lfw::Graph;
+use lfw::ConnectionType;
+use lfw::HideoutDistribution;
+use lfw::LocationSet;
+use lfw::location_set;
 use lfw::Night;
-use lfw::night::Location::*;
+use lfw::night::ClueObservation;
 use lfw::night::MoveType;
+use std::cmp;
+use std::collections::HashMap;
 use std::collections::HashSet;
 use std::collections::VecDeque;
 use std::iter::FromIterator;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+
+
+// Number of moves enumerated before splitting the search across threads. Each move multiplies the
+// number of independent subtrees by around 6, so two moves are plenty to keep threads busy.
+const PARALLEL_SPLIT_DEPTH: u32 = 2;
+
+/// Distance between two locations that can't be reached from one another
+pub const UNREACHABLE: u32 = u32::MAX;
 
 
 #[derive(Debug)]
@@ -15,85 +31,394 @@ struct LocationState {
 }
 
 
+/// A path taken by Jack during a night, starting on the murder location and with one location
+/// per move.
+pub type Path = Vec<u32>;
+
+
+/// A move being explored by `Paths`, with the locations Jack could go to for it
 #[derive(Debug)]
+struct PathFrame<'a> {
+    connections: location_set::IntoIter,
+    explained_clues: Vec<&'a ClueObservation>,
+}
+
+
+/// Iterator over Jack's possible paths for a night, see `Solver::paths`.
+///
+/// This is the same depth-first search as `Solver::solve_night`, only with an explicit stack so
+/// that it can stop after each path found.
+#[derive(Debug)]
+pub struct Paths<'a> {
+    solver: &'a Solver,
+    night: &'a Night,
+    candidate_hideouts: Option<&'a LocationSet>,
+    current_path: Path,
+    required_clues: HashSet<&'a ClueObservation>,
+    stack: Vec<PathFrame<'a>>,
+    // Only used for nights without any move, where the murder location is the only path
+    pending_path: Option<Path>,
+}
+
+
+impl<'a> Paths<'a> {
+    fn new(solver: &'a Solver, night: &'a Night, candidate_hideouts: Option<&'a LocationSet>) -> Paths<'a> {
+        let mut paths = Paths {
+            solver: solver,
+            night: night,
+            candidate_hideouts: candidate_hideouts,
+            current_path: vec![*night.murder_location()],
+            required_clues: Solver::clues_to_explain(night),
+            stack: Vec::new(),
+            pending_path: None,
+        };
+
+        if *night.jack_nb_moves() == 0 {
+            if Solver::is_path_complete(&paths.current_path, &paths.required_clues, candidate_hideouts) {
+                paths.pending_path = Some(paths.current_path.clone());
+            }
+        } else if let Some(frame) = paths.frame_for_turn(&1) {
+            paths.stack.push(frame);
+        }
+        paths
+    }
+
+    // Builds the frame for the move of `turn`, from the last location of the current path.
+    // Returns `None` if the current path can't lead to a solution.
+    fn frame_for_turn(&self, turn: &u32) -> Option<PathFrame<'a>> {
+        // unwrap() because current_path shouldn't be empty, so we're happy to panic if that's the case
+        let current_location = *(self.current_path.last().unwrap());
+        if !self.solver.is_path_still_possible(self.night, &current_location, &self.required_clues, self.candidate_hideouts, turn) {
+            return None;
+        }
+
+        Some(PathFrame {
+            connections: self.solver.next_locations_for_turn(self.night, &current_location, turn).into_iter(),
+            explained_clues: Vec::new(),
+        })
+    }
+}
+
+
+impl<'a> Iterator for Paths<'a> {
+    type Item = Path;
+
+    fn next(&mut self) -> Option<Path> {
+        if let Some(path) = self.pending_path.take() {
+            return Some(path);
+        }
+
+        while !self.stack.is_empty() {
+            // The frame at depth N explores the move for turn N, so it has pushed a location onto
+            // the current path when the path is longer than the stack.
+            let depth = self.stack.len();
+            let turn = depth as u32;
+            {
+                let frame = self.stack.last_mut().unwrap();
+                if self.current_path.len() > depth {
+                    self.current_path.pop();
+                    for clue in frame.explained_clues.drain(..) {
+                        self.required_clues.insert(clue);
+                    }
+                }
+                let connection = match frame.connections.next() {
+                    Some(cnx) => cnx,
+                    None => {
+                        self.stack.pop();
+                        continue;
+                    }
+                };
+                self.current_path.push(connection);
+                frame.explained_clues = Solver::clues_explained_by(&self.required_clues, &connection, &turn);
+                for clue in &frame.explained_clues {
+                    self.required_clues.remove(clue);
+                }
+            }
+
+            if turn == *self.night.jack_nb_moves() {
+                if Solver::is_path_complete(&self.current_path, &self.required_clues, self.candidate_hideouts) {
+                    return Some(self.current_path.clone());
+                }
+            } else if let Some(frame) = self.frame_for_turn(&(turn + 1)) {
+                self.stack.push(frame);
+            }
+        }
+        None
+    }
+}
+
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct Solver {
     graph: Graph,
     shortest_distances: Vec<Vec<u32>>,
+    regular_distances: Vec<Vec<u32>>,
+    alleyway_distances: Vec<Vec<u32>>,
 }
 
 impl Solver {
     pub fn from_graph(graph: Graph) -> Solver {
         let shortest_distances = Solver::precompute_shortest_distances(&graph);
+        let regular_distances = Solver::precompute_shortest_distances_for_type(&graph, ConnectionType::Regular);
+        let alleyway_distances = Solver::precompute_shortest_distances_for_type(&graph, ConnectionType::Alleyway);
         Solver {
             graph: graph,
-            shortest_distances: shortest_distances
+            shortest_distances: shortest_distances,
+            regular_distances: regular_distances,
+            alleyway_distances: alleyway_distances,
         }
     }
 
-    // This method uses the Floyd–Warshall algorithm to precompute the shortest path between
-    // all locations (graph nodes/vertices).
-    #[allow(unknown_lints)]
-    #[allow(needless_range_loop)]
+    /// Precomputes the shortest distance between all locations (graph nodes/vertices), using any
+    /// connection. Locations that can't be reached are `UNREACHABLE` from each other.
     pub fn precompute_shortest_distances(graph: &Graph) -> Vec<Vec<u32>> {
-        // Create a bidemensional table, 1000 value.
-        let graph_size = 195;
-        let mut dist: Vec<Vec<u32>> = vec![vec![1000; graph_size]; graph_size];
-
-        // Initialises the graph
-        // Set distance (v,v) = 0 on all locations (v [vertices])
-        // Set distance (u,v) = (v,u) = 1 on all locations (u) and their connections (v)
-        for location in 0..graph_size {
-            dist[location][location] = 0;
-
-            if let Some(all_cnx) = graph.all_connections_for_location(&(location as u32 + 1)) {
-                for cnx in all_cnx {
-                    let connection = *cnx as usize - 1;
-                    dist[location][connection] = 1;
-                    dist[connection][location] = 1;
+        Solver::breadth_first_distances(graph, |location| graph.all_connections_for_location(location))
+    }
+
+    /// Same as `precompute_shortest_distances`, only using connections of the given type
+    pub fn precompute_shortest_distances_for_type(graph: &Graph, cnx_type: ConnectionType) -> Vec<Vec<u32>> {
+        match cnx_type {
+            ConnectionType::Regular => {
+                Solver::breadth_first_distances(graph, |location| graph.connection_set_for_location(location).cloned())
+            },
+            ConnectionType::Alleyway => {
+                Solver::breadth_first_distances(graph, |location| graph.alleyway_connection_set_for_location(location).cloned())
+            },
+        }
+    }
+
+    // Runs a breadth-first search from each location, which is much cheaper than Floyd–Warshall
+    // on a board where each location only has a handful of connections.
+    fn breadth_first_distances<F>(graph: &Graph, connections_for_location: F) -> Vec<Vec<u32>>
+        where F: Fn(&u32) -> Option<LocationSet>
+    {
+        let graph_size = graph.nb_locations() as usize;
+        let mut dist: Vec<Vec<u32>> = vec![vec![UNREACHABLE; graph_size]; graph_size];
+
+        for source in 1..=graph.nb_locations() {
+            let source_dist = &mut dist[source as usize - 1];
+            let mut visited = LocationSet::from_iter(Some(source));
+            let mut frontier = visited.clone();
+            let mut distance = 0;
+            while !frontier.is_empty() {
+                for location in frontier.iter() {
+                    source_dist[location as usize - 1] = distance;
                 }
+                frontier = frontier.iter().fold(LocationSet::new(), |mut next, location| {
+                    if let Some(cnx) = connections_for_location(&location) {
+                        next.union_with(&cnx);
+                    }
+                    next
+                }).difference(&visited);
+                visited.union_with(&frontier);
+                distance += 1;
+            }
+        }
+
+        dist
+    }
+
+    /// Whether the distance tables have an entry for every pair of locations of a consistent
+    /// graph, so that looking up distances can't go out of bounds. Only solvers decoded from
+    /// elsewhere (such as a corrupt cache) can fail this.
+    pub fn is_consistent(&self) -> bool {
+        let nb_locations = self.graph.nb_locations() as usize;
+        self.graph.is_consistent() &&
+            [&self.shortest_distances, &self.regular_distances, &self.alleyway_distances].iter().all(|distances| {
+                distances.len() == nb_locations && distances.iter().all(|row| row.len() == nb_locations)
+            })
+    }
+
+    pub fn graph(&self) -> &Graph {
+        &(self.graph)
+    }
+
+    fn distance_between_locations(distances: &[Vec<u32>], location: &u32, destination: &u32) -> u32 {
+        distances[*location as usize - 1][*destination as usize - 1]
+    }
+
+    // Same as `distance_between_locations`, for locations that may not be on the board
+    fn checked_distance_between_locations(distances: &[Vec<u32>], location: &u32, destination: &u32) -> Option<u32> {
+        let idx = |loc: &u32| (*loc as usize).checked_sub(1);
+        match (idx(location), idx(destination)) {
+            (Some(loc_idx), Some(dest_idx)) => {
+                distances.get(loc_idx).and_then(|row| row.get(dest_idx)).cloned().filter(|&distance| distance != UNREACHABLE)
+            },
+            _ => None,
+        }
+    }
+
+    /// Returns the number of moves between two locations using any connection, or `None` if
+    /// one can't be reached from the other (or either isn't on the board)
+    pub fn shortest_distance(&self, location: &u32, destination: &u32) -> Option<u32> {
+        Solver::checked_distance_between_locations(&self.shortest_distances, location, destination)
+    }
+
+    /// Same as `shortest_distance`, only using connections of the given type
+    pub fn shortest_distance_for_type(&self, location: &u32, destination: &u32, cnx_type: ConnectionType) -> Option<u32> {
+        let distances = match cnx_type {
+            ConnectionType::Regular => &self.regular_distances,
+            ConnectionType::Alleyway => &self.alleyway_distances,
+        };
+        Solver::checked_distance_between_locations(distances, location, destination)
+    }
+
+    /// Returns all the routes going from a location to another in as few moves as possible
+    /// (using any connection), in ascending order of the locations they go through.
+    pub fn shortest_routes(&self, location: &u32, destination: &u32) -> Vec<Path> {
+        let distance = match self.shortest_distance(location, destination) {
+            Some(d) => d,
+            None => return Vec::new(),
+        };
+
+        // Each step of a shortest route gets one move closer to the destination
+        let mut routes = vec![vec![*location]];
+        for moves_left in (0..distance).rev() {
+            routes = routes.into_iter().flat_map(|route| {
+                // unwrap() because routes are never empty, and only contain valid locations
+                let route_end = *route.last().unwrap();
+                let next_steps = self.graph.all_connections_for_location(&route_end).unwrap();
+                next_steps.iter().filter(|step| {
+                    self.shortest_distance(step, destination) == Some(moves_left)
+                }).map(|step| {
+                    let mut next_route = route.clone();
+                    next_route.push(step);
+                    next_route
+                }).collect::<Vec<_>>()
+            }).collect();
+        }
+        routes
+    }
+
+    /// Whether Jack, on `location` before the move for `first_turn`, could be on `destination`
+    /// after the move for `last_turn` at the latest, given the types of these moves.
+    ///
+    /// This only checks distances, so it can answer `true` for destinations that are actually out
+    /// of reach, but never `false` for destinations that can be reached.
+    fn is_within_reach(&self, night: &Night, location: &u32, destination: &u32, first_turn: &u32, last_turn: &u32) -> bool {
+        let (mut nb_regular, mut nb_alleyways) = (0, 0);
+        for turn in *first_turn..=*last_turn {
+            match night.jack_move_type_for_turn(&turn) {
+                MoveType::Regular => nb_regular += 1,
+                MoveType::Alleyway => nb_alleyways += 1,
+                // A carriage is two regular moves in a row
+                MoveType::Carriage => nb_regular += 2,
             }
         }
 
-        // Iterate over intermediate locations
-        for k in 0..graph_size {
-            // Iterate over starting locations
-            for i in 0..graph_size {
-                // Iterate over end locations
-                for j in 0..graph_size {
-                    // If distance i->k + k->j is smaller that currently known i->j,
-                    // then keep that distance as the minimum
-                    let dist_ikj = dist[i][k] + dist[k][j];
-                    if dist[i][j] > dist_ikj {
-                        dist[i][j] = dist_ikj;
-                        dist[j][i] = dist_ikj;
+        let (distances, reach) = match (nb_regular, nb_alleyways) {
+            (_, 0) => (&self.regular_distances, nb_regular),
+            (0, _) => (&self.alleyway_distances, nb_alleyways),
+            _ => (&self.shortest_distances, nb_regular + nb_alleyways),
+        };
+        Solver::distance_between_locations(distances, location, destination) <= reach
+    }
+
+    // Regular moves can't go through crossings occupied by the police, while alleyways and
+    // carriages aren't affected by them.
+    fn connections_for_turn(&self, night: &Night, location: &u32, turn: &u32) -> Option<LocationSet> {
+        match night.jack_move_type_for_turn(turn) {
+            MoveType::Alleyway => self.graph.alleyway_connection_set_for_location(location).cloned(),
+            MoveType::Carriage => self.graph.carriage_connections_for_location(location),
+            MoveType::Regular => {
+                let police_crossings = night.police_crossings_for_turn(turn);
+                self.graph.connection_set_for_location(location).map(|connections| {
+                    if police_crossings.is_empty() {
+                        connections.clone()
+                    } else {
+                        LocationSet::from_iter(connections.iter().filter(|cnx| {
+                            !self.graph.is_regular_move_blocked(location, cnx, police_crossings)
+                        }))
                     }
-                }
+                })
+            },
+        }
+    }
+
+    /// Returns the locations Jack could be on after making the move for `turn` from `location`,
+    /// given the failed clues and arrests of the night.
+    fn next_locations_for_turn(&self, night: &Night, location: &u32, turn: &u32) -> LocationSet {
+        // unwrap() because paths only ever contain valid locations
+        let mut connections = self.connections_for_turn(night, location, turn).unwrap();
+        match night.jack_possible_locations_for_turn(turn) {
+            Some(possible_locations) => connections.intersect_with(possible_locations),
+            // Jack doesn't move outside of the night
+            None => connections = LocationSet::new(),
+        }
+        connections
+    }
+
+    /// Whether Jack, on `location` before making the move for `turn`, can still go through all
+    /// the clues left to explain and end on one of the candidate hideouts.
+    fn is_path_still_possible(&self,
+        night: &Night,
+        location: &u32,
+        required_clues: &HashSet<&ClueObservation>,
+        candidate_hideouts: Option<&LocationSet>,
+        turn: &u32) -> bool
+    {
+        // Simple but big optimisation here: if there's no way to go from the current location to
+        // any of the clues before the turn it was found on, then drop the current path and do not
+        // continue further.
+        for clue in required_clues.iter() {
+            if turn >= clue.found_on_turn() {
+                return false;
+            }
+            let last_turn = clue.found_on_turn() - 1;
+            if !self.is_within_reach(night, location, clue.location(), turn, &last_turn) {
+                return false;
             }
         }
 
-        dist
+        // Same thing with hideouts: if none of the hideouts still possible can be reached with the
+        // moves left (including the current one), there's no point in continuing this path.
+        if let Some(hideouts) = candidate_hideouts {
+            if !hideouts.iter().any(|hideout| self.is_within_reach(night, location, &hideout, turn, night.jack_nb_moves())) {
+                return false;
+            }
+        }
+
+        true
     }
 
-    fn distance_between_locations(&self, location: &u32, destination: &u32) -> u32 {
-        self.shortest_distances[*location as usize - 1][*destination as usize - 1]
+    // Going through a clue's location before it was found explains that clue
+    fn clues_explained_by<'a>(required_clues: &HashSet<&'a ClueObservation>, location: &u32, turn: &u32) -> Vec<&'a ClueObservation> {
+        Vec::from_iter(required_clues.iter().filter(|clue| {
+            clue.location() == location && turn < clue.found_on_turn()
+        }).cloned())
+    }
+
+    // Check that the path we're testing goes through all the clues found for Jack (it being
+    // empty means there are no clues left to be explained), and ends on a candidate hideout.
+    fn is_path_complete(path: &[u32], required_clues: &HashSet<&ClueObservation>, candidate_hideouts: Option<&LocationSet>) -> bool {
+        let ends_on_hideout = candidate_hideouts.is_none_or(|hideouts| {
+            path.last().is_some_and(|location| hideouts.contains(location))
+        });
+        required_clues.is_empty() && ends_on_hideout
+    }
+
+    // Clues found on the murder location are already explained by the murder itself
+    fn clues_to_explain(night: &Night) -> HashSet<&ClueObservation> {
+        HashSet::from_iter(night.jack_clues().iter().filter(|clue| {
+            clue.location() != night.murder_location() || *clue.found_on_turn() == 0
+        }))
+    }
+
+    /// Lazily iterates over all of Jack's possible paths for a night, finding them as they are
+    /// needed instead of holding all of them in memory.
+    pub fn paths<'a>(&'a self, night: &'a Night) -> Paths<'a> {
+        Paths::new(self, night, None)
     }
 
-    /**
-     * TODO: Solve multiple nights. Now that we have a pre-calculated distance between each
-     * location, we can further optimise searched by adding potential hideouts to the list of
-     * locations we're require to have visited.
-     */
     pub fn solve_night(&self, night: Night) -> Option<Vec<Vec<u32>>> {
         let mut possible_paths = Vec::<Vec<u32>>::new();
-        let mut current_path = Vec::<u32>::new();
-        let mut required_locations = HashSet::from_iter(night.jack_known_locations().iter());
-
-        current_path.push(*night.murder_location());
+        let mut current_path = vec![*night.murder_location()];
+        let mut required_clues = Solver::clues_to_explain(&night);
 
         self.find_next_possible_locations(&night,
                                           &mut possible_paths,
                                           &mut current_path,
-                                          &mut required_locations,
+                                          &mut required_clues,
                                           &1);
 
         if possible_paths.is_empty() {
@@ -102,24 +427,261 @@ impl Solver {
         Some(possible_paths)
     }
 
+    /// Solves a night, and counts how many of the possible paths end on each hideout
+    pub fn solve_night_hideouts(&self, night: Night) -> Option<HideoutDistribution> {
+        self.count_enumerated_paths_per_hideout(&night)
+    }
+
+    fn count_enumerated_paths_per_hideout(&self, night: &Night) -> Option<HideoutDistribution> {
+        let mut distribution = HideoutDistribution::new();
+        for path in self.paths(night) {
+            distribution.add_path(&path);
+        }
+
+        if distribution.is_empty() {
+            return None;
+        }
+        Some(distribution)
+    }
+
+    /// Counts how many of a night's possible paths end on each hideout, without enumerating them
+    ///
+    /// Two paths that reach the same location on the same turn, with the same clues left to
+    /// explain, can be continued in exactly the same ways. So instead of following each path, this
+    /// keeps the number of paths for each (location, clues left) state, one turn after another.
+    /// Clues left are stored as a bitmask, so nights with more than 64 clues fall back to
+    /// enumerating paths, which gives the same result but is much slower.
+    pub fn count_paths_per_hideout(&self, night: &Night) -> Option<HideoutDistribution> {
+        self.count_paths_per_hideout_parallel(night, 1)
+    }
+
+    /// Same as `count_paths_per_hideout`, but splits the states of each turn between `nb_threads`
+    /// threads.
+    pub fn count_paths_per_hideout_parallel(&self, night: &Night, nb_threads: usize) -> Option<HideoutDistribution> {
+        let clues = night.jack_clues();
+        if clues.len() > 64 {
+            return self.count_enumerated_paths_per_hideout(night);
+        }
+
+        let all_clues_mask = if clues.len() == 64 { !0 } else { (1u64 << clues.len()) - 1 };
+        let start_state = (*night.murder_location(), all_clues_mask & !Solver::explained_clues_mask(night, night.murder_location(), &0));
+        let mut nb_paths_per_state: Vec<((u32, u64), u64)> = vec![(start_state, 1)];
+
+        for turn in 1..=*night.jack_nb_moves() {
+            let next_nb_paths_per_state = if nb_threads <= 1 || nb_paths_per_state.len() < nb_threads {
+                self.next_nb_paths_per_state(night, &turn, &nb_paths_per_state)
+            } else {
+                let chunk_size = nb_paths_per_state.len().div_ceil(nb_threads);
+                thread::scope(|scope| {
+                    let workers: Vec<_> = nb_paths_per_state.chunks(chunk_size).map(|states| {
+                        scope.spawn(move || self.next_nb_paths_per_state(night, &turn, states))
+                    }).collect();
+
+                    let mut merged_nb_paths_per_state = HashMap::new();
+                    for worker in workers {
+                        // A worker can only panic if the solver itself does, so propagate it
+                        for (state, nb_paths) in worker.join().unwrap() {
+                            *merged_nb_paths_per_state.entry(state).or_insert(0) += nb_paths;
+                        }
+                    }
+                    merged_nb_paths_per_state
+                })
+            };
+            nb_paths_per_state = next_nb_paths_per_state.into_iter().collect();
+        }
+
+        let mut distribution = HideoutDistribution::new();
+        for &((hideout, clues_left), nb_paths) in &nb_paths_per_state {
+            if clues_left == 0 {
+                distribution.add_paths_for_hideout(&hideout, nb_paths);
+            }
+        }
+
+        if distribution.is_empty() {
+            return None;
+        }
+        Some(distribution)
+    }
+
+    // Bitmask of the clues explained by being on `location` after the move for `turn`
+    fn explained_clues_mask(night: &Night, location: &u32, turn: &u32) -> u64 {
+        night.jack_clues().iter().enumerate().filter(|&(_, clue)| {
+            clue.location() == location && turn < clue.found_on_turn()
+        }).fold(0, |mask, (idx, _)| mask | (1 << idx))
+    }
+
+    // Moves each (location, clues left) state on to the locations Jack can reach for `turn`
+    fn next_nb_paths_per_state(&self, night: &Night, turn: &u32, nb_paths_per_state: &[((u32, u64), u64)]) -> HashMap<(u32, u64), u64> {
+        let clues = night.jack_clues();
+        let mut next_nb_paths_per_state = HashMap::new();
+        for &((location, clues_left), nb_paths) in nb_paths_per_state {
+            // Drop states with a clue that can't be explained anymore, i.e. that was found
+            // before or during the turn being looked at.
+            let has_missed_clue = clues.iter().enumerate().any(|(idx, clue)| {
+                clues_left & (1 << idx) != 0 && turn >= clue.found_on_turn()
+            });
+            if has_missed_clue {
+                continue;
+            }
+
+            for connection in self.next_locations_for_turn(night, &location, turn).iter() {
+                let next_state = (connection, clues_left & !Solver::explained_clues_mask(night, &connection, turn));
+                *next_nb_paths_per_state.entry(next_state).or_insert(0) += nb_paths;
+            }
+        }
+        next_nb_paths_per_state
+    }
+
+    /// Solves all the nights of a game, and returns the hideouts consistent with every one of them
+    ///
+    /// Jack has to go back to the same hideout at the end of each night, so the hideouts found for
+    /// a night are used as the only possible end locations for the following nights.
+    ///
+    /// @param nights The nights of the game, in the order they were played
+    pub fn solve_game(&self, nights: Vec<Night>) -> Option<Vec<u32>> {
+        let mut candidate_hideouts: Option<LocationSet> = None;
+
+        for night in &nights {
+            // Only the end of each path matters, so paths are dropped as soon as they're found
+            let night_hideouts = Paths::new(self, night, candidate_hideouts.as_ref()).fold(LocationSet::new(), |mut hideouts, path| {
+                // unwrap() because paths always start with the murder location
+                hideouts.insert(path.last().unwrap());
+                hideouts
+            });
+            if night_hideouts.is_empty() {
+                return None;
+            }
+            candidate_hideouts = Some(night_hideouts);
+        }
+
+        // No nights means no hideout could be found
+        candidate_hideouts.map(|hideouts| hideouts.iter().collect())
+    }
+
+    /// Same as `solve_game`, but with each night's paths counted on `nb_threads` threads (see
+    /// `count_paths_per_hideout_parallel`) instead of being enumerated.
+    pub fn solve_game_parallel(&self, nights: Vec<Night>, nb_threads: usize) -> Option<Vec<u32>> {
+        let mut candidate_hideouts: Option<LocationSet> = None;
+
+        for night in &nights {
+            let mut night_hideouts = match self.count_paths_per_hideout_parallel(night, nb_threads) {
+                Some(distribution) => LocationSet::from_iter(distribution.hideouts()),
+                None => return None,
+            };
+            if let Some(ref hideouts) = candidate_hideouts {
+                night_hideouts.intersect_with(hideouts);
+            }
+            if night_hideouts.is_empty() {
+                return None;
+            }
+            candidate_hideouts = Some(night_hideouts);
+        }
+
+        // No nights means no hideout could be found
+        candidate_hideouts.map(|hideouts| hideouts.iter().collect())
+    }
+
+    /// Same as `solve_night`, but with the search split across `nb_threads` threads
+    ///
+    /// The paths for the first moves of the night are enumerated upfront, and each thread then
+    /// takes the next of these prefixes still to be explored until there are none left. Results
+    /// are merged back in the order of the prefixes, so the output is the same as `solve_night`.
+    pub fn solve_night_parallel(&self, night: Night, nb_threads: usize) -> Option<Vec<Vec<u32>>> {
+        if nb_threads <= 1 {
+            return self.solve_night(night);
+        }
+
+        let split_depth = cmp::min(PARALLEL_SPLIT_DEPTH, *night.jack_nb_moves());
+        let prefixes = self.path_prefixes(&night, split_depth);
+        let next_prefix_idx = AtomicUsize::new(0);
+        let mut paths_per_prefix: Vec<Vec<Vec<u32>>> = vec![Vec::new(); prefixes.len()];
+
+        thread::scope(|scope| {
+            let workers: Vec<_> = (0..nb_threads).map(|_| {
+                scope.spawn(|| {
+                    let mut found_paths = Vec::new();
+                    loop {
+                        let prefix_idx = next_prefix_idx.fetch_add(1, Ordering::SeqCst);
+                        let prefix = match prefixes.get(prefix_idx) {
+                            Some(p) => p,
+                            None => break,
+                        };
+                        found_paths.push((prefix_idx, self.solve_night_from_prefix(&night, prefix)));
+                    }
+                    found_paths
+                })
+            }).collect();
+
+            for worker in workers {
+                // A worker can only panic if the solver itself does, so propagate it
+                for (prefix_idx, paths) in worker.join().unwrap() {
+                    paths_per_prefix[prefix_idx] = paths;
+                }
+            }
+        });
+
+        let possible_paths: Vec<Vec<u32>> = paths_per_prefix.into_iter().flat_map(|paths| paths.into_iter()).collect();
+        if possible_paths.is_empty() {
+            return None;
+        }
+        Some(possible_paths)
+    }
+
+    // Enumerates the beginnings of Jack's possible paths, up to the move for `last_turn`, in the
+    // same order as `find_next_possible_locations` would explore them.
+    fn path_prefixes(&self, night: &Night, last_turn: u32) -> Vec<Vec<u32>> {
+        let mut prefixes = vec![vec![*night.murder_location()]];
+        for turn in 1..=last_turn {
+            prefixes = prefixes.into_iter().flat_map(|prefix| {
+                // unwrap() because prefixes are never empty
+                let location = *prefix.last().unwrap();
+                self.next_locations_for_turn(night, &location, &turn).iter().map(|cnx| {
+                    let mut next_prefix = prefix.clone();
+                    next_prefix.push(cnx);
+                    next_prefix
+                }).collect::<Vec<_>>()
+            }).collect();
+        }
+        prefixes
+    }
+
+    // Finds all of Jack's possible paths starting with `prefix`
+    fn solve_night_from_prefix(&self, night: &Night, prefix: &[u32]) -> Vec<Vec<u32>> {
+        let mut possible_paths = Vec::<Vec<u32>>::new();
+        let mut current_path = prefix.to_vec();
+        let mut required_clues = Solver::clues_to_explain(night);
+
+        for (turn, location) in prefix.iter().enumerate().skip(1) {
+            for clue in Solver::clues_explained_by(&required_clues, location, &(turn as u32)) {
+                required_clues.remove(clue);
+            }
+        }
+
+        self.find_next_possible_locations(night,
+                                          &mut possible_paths,
+                                          &mut current_path,
+                                          &mut required_clues,
+                                          &(prefix.len() as u32));
+
+        possible_paths
+    }
+
     /// Recursively finds Jack's next possible locations
     ///
     /// @param night The night to solve locations for
     /// @param possible_paths The list of paths we've determined were possible
     /// @param current_path The current patht to append onto
-    /// @param required_locations The list of locations that still need to be visited
+    /// @param required_clues The clues Jack's path still needs to go through
     /// @param current_turn The current turn/move being looked at
-    fn find_next_possible_locations(&self,
-        night: &Night,
+    fn find_next_possible_locations<'a>(&self,
+        night: &'a Night,
         possible_paths: &mut Vec<Vec<u32>>,
         current_path: &mut Vec<u32>,
-        required_locations: &mut HashSet<&u32>,
+        required_clues: &mut HashSet<&'a ClueObservation>,
         current_turn: &u32)
     {
         if current_turn > night.jack_nb_moves() {
-            // Check that the path we're testing includes all required locations for Jack.
-            // It being empty means there are no required locations left to be visited.
-            if required_locations.is_empty() {
+            if Solver::is_path_complete(current_path, required_clues, None) {
                 possible_paths.push(current_path.clone());
             }
             return
@@ -127,42 +689,25 @@ impl Solver {
 
         // unwrap() because current_path shouldn't be empty, so we're happy to panic if that's the case
         let current_location = *(current_path.last().unwrap());
-        let moves_left = night.jack_nb_moves() - current_turn;
 
-        // Simple but big optimisation here: if there's no way to go from the current location to
-        // any of the required locations, then drop the current path and do not continue further.
-        for required_location in required_locations.iter() {
-            if self.distance_between_locations(&current_location, required_location) > moves_left {
-                return
-            }
+        if !self.is_path_still_possible(night, &current_location, required_clues, None, current_turn) {
+            return
         }
 
-        let connections = match night.jack_move_type_for_turn(current_turn) {
-            MoveType::Alleyway => self.graph.alleyway_connections_for_location(&(current_location)),
-            MoveType::Regular => self.graph.connections_for_location(&(current_location)),
-        }.unwrap();
-
-        for connection in connections {
-            let should_visit = match *night.jack_visit_status_for_location(connection) {
-                NeverVisitedAsOf(ref turn) | VisitedAfter(ref turn) if current_turn <= turn => false,
-                _ => true
-            };
-            if should_visit {
-                current_path.push(*connection);
-                let removed_required_location = if required_locations.contains(&current_location) {
-                    required_locations.take(&current_location)
-                } else {
-                    None
-                };
-                self.find_next_possible_locations(night,
-                                                  possible_paths,
-                                                  current_path,
-                                                  required_locations,
-                                                  &(current_turn + 1));
-                current_path.pop();
-                if let Some(known_location) = removed_required_location {
-                    required_locations.insert(known_location);
-                }
+        for connection in self.next_locations_for_turn(night, &current_location, current_turn).iter() {
+            current_path.push(connection);
+            let explained_clues = Solver::clues_explained_by(required_clues, &connection, current_turn);
+            for clue in &explained_clues {
+                required_clues.remove(clue);
+            }
+            self.find_next_possible_locations(night,
+                                              possible_paths,
+                                              current_path,
+                                              required_clues,
+                                              &(current_turn + 1));
+            current_path.pop();
+            for clue in explained_clues {
+                required_clues.insert(clue);
             }
         }
     }
@@ -180,7 +725,13 @@ impl Solver {
 
         while let Some(state) = queue.pop_front() {
             if state.current_turn > max_nb_moves {
-                if night.jack_known_locations().iter().all(|known_loc| state.current_path.contains(known_loc)) {
+                // A clue found on turn N means Jack was there at some turn before N, i.e. within
+                // the first N locations of the path (the murder location being turn 0).
+                let explains_all_clues = night.jack_clues().iter().all(|clue| {
+                    let nb_turns_before_clue = (*clue.found_on_turn() as usize).min(state.current_path.len());
+                    state.current_path[..nb_turns_before_clue].contains(clue.location())
+                });
+                if explains_all_clues {
                     possible_paths.push(state.current_path.clone());
                 }
                 continue;
@@ -189,25 +740,14 @@ impl Solver {
             // unwrap() because `current_path` shouldn't be empty, so we're happy to panic if that's the case
             let current_location = state.current_path.last().unwrap();
 
-            let connections = match night.jack_move_type_for_turn(&(state.current_turn)) {
-                MoveType::Alleyway => self.graph.alleyway_connections_for_location(current_location),
-                MoveType::Regular => self.graph.connections_for_location(current_location),
-            }.unwrap();
-
-            for connection in connections {
-                let should_visit = match *night.jack_visit_status_for_location(connection) {
-                    NeverVisitedAsOf(ref turn) | VisitedAfter(ref turn) if state.current_turn <= *turn => false,
-                    _ => true
+            for connection in self.next_locations_for_turn(&night, current_location, &(state.current_turn)).iter() {
+                let mut current_path = state.current_path.clone();
+                current_path.push(connection);
+                let new_state = LocationState {
+                    current_turn: state.current_turn + 1,
+                    current_path: current_path,
                 };
-                if should_visit {
-                    let mut current_path = state.current_path.clone();
-                    current_path.push(*connection);
-                    let new_state = LocationState {
-                        current_turn: state.current_turn + 1,
-                        current_path: current_path,
-                    };
-                    queue.push_back(new_state);
-                }
+                queue.push_back(new_state);
             }
         }
 
@@ -2,6 +2,9 @@
 
 extern crate serde;
 extern crate serde_json;
+extern crate bincode;
+extern crate toml;
+extern crate serde_yaml_ng;
 #[macro_use]
 extern crate serde_derive;
 
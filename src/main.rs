@@ -4,19 +4,18 @@ extern crate lfw_solver;
 extern crate serde_json;
 extern crate getopts;
 use lfw_solver::*;
-use getopts::Options;
+use getopts::{Matches, Options};
 use std::{env, process};
-use std::collections::HashSet;
-use std::iter::FromIterator;
-use std::io::BufReader;
+use std::io;
+use std::io::{BufReader, BufWriter};
+use std::fs;
 use std::fs::File;
 use std::path::Path;
-use serde_json::*;
 use serde_json::Value as JsonValue;
 
 
 fn print_usage(options: Options, program_executable: &str, print_small_usage: bool) {
-    let short_usage = options.short_usage(program_executable);
+    let short_usage = format!("{} [solve|validate|dot|diff OLD_GRAPH NEW_GRAPH|graph QUERY|upgrade]", options.short_usage(program_executable));
     if print_small_usage {
         println!("{}", short_usage);
     } else {
@@ -25,51 +24,290 @@ fn print_usage(options: Options, program_executable: &str, print_small_usage: bo
 }
 
 
-fn parse_json_file_at_path<P: AsRef<Path>>(json_path: P) -> Result<JsonValue> {
-    let graph_file = try!(File::open(json_path));
-    let reader = BufReader::new(graph_file);
-    serde_json::from_reader(reader)
+// TOML files can't have an array at their top level, so the nights of a game can also be listed
+// in a `nights` array of tables (`[[nights]]` in TOML)
+const GAME_NIGHTS_KEY: &'static str = "nights";
+
+
+// The format of a data file is given by `format_option` (`--graph-format` or `--data-format`),
+// or guessed from its extension
+fn data_format_for_path(path: &str, format_option: &str, matches: &Matches) -> lfw::DataFormat {
+    match matches.opt_str(format_option) {
+        None => lfw::DataFormat::from_path(path),
+        Some(name) => match lfw::DataFormat::from_name(&name) {
+            Some(format) => format,
+            None => {
+                println_stderr!("Unknown data format: {} (must be json, toml or yaml)", name);
+                process::exit(1);
+            }
+        },
+    }
 }
 
 
-fn main() {
-    let args: Vec<String> = env::args().collect();
-    let program_executable = args[0].clone();
+fn parse_data_file_at_path(data_path: &str, format: lfw::DataFormat) -> Result<JsonValue, String> {
+    let mut source = String::new();
+    try!(File::open(data_path).and_then(|f| BufReader::new(f).read_to_string(&mut source)).map_err(|e| e.to_string()));
+    format.parse(&source).map_err(|e| e.to_string())
+}
 
-    let mut options = Options::new();
-    options.optflag("h", "help", "Display this help message");
-    options.optopt("g", "graph", "Path to a JSON file representing the LfW graph", "GRAPH");
-    options.optopt("d",
-                   "game-data",
-                   "Path to a JSON file containing the game data",
-                   "GAME_DATA");
 
-    let matches = match options.parse(&args[1..]) {
-        Ok(m) => m,
+// Game data files hold either a single night, or all the nights of a game as an array (or as a
+// `nights` table, which is turned into that array)
+fn parse_game_data_at_path(data_path: &str, format: lfw::DataFormat) -> Result<JsonValue, String> {
+    let json = try!(parse_data_file_at_path(data_path, format));
+    match json {
+        JsonValue::Object(ref object) if object.len() == 1 && object.get(GAME_NIGHTS_KEY).is_some_and(|n| n.is_array()) => {
+            Ok(object[GAME_NIGHTS_KEY].clone())
+        },
+        json => Ok(json),
+    }
+}
+
+
+// Writes to a temporary file first, and only replaces the file at `path` once that's done, so
+// that a failure can't leave it half written
+fn write_file_atomically(path: &str, contents: &[u8]) -> io::Result<()> {
+    let temp_path = format!("{}.tmp", path);
+    let written = File::create(&temp_path).and_then(|mut f| {
+        try!(f.write_all(contents));
+        f.sync_all()
+    }).and_then(|_| fs::rename(&temp_path, path));
+    if written.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    written
+}
+
+
+fn read_cache_at_path<P: AsRef<Path>>(cache_path: P, source_hash: &u64) -> Option<lfw::Solver> {
+    let cache_file = match File::open(cache_path) {
+        Ok(f) => f,
+        Err(_) => return None,
+    };
+    let mut reader = BufReader::new(cache_file);
+    match lfw::SolverCache::from_reader(&mut reader) {
+        Ok(ref cache) if cache.source_hash() != source_hash => None,
+        Ok(cache) => Some(cache.into_solver()),
         Err(e) => {
-            println_stderr!("{}", e);
-            print_usage(options, &program_executable, true);
-            process::exit(1);
+            println_stderr!("Ignoring unreadable graph cache: {}", e);
+            None
+        }
+    }
+}
+
+
+fn write_cache_at_path<P: AsRef<Path>>(cache_path: P, cache: &lfw::SolverCache) {
+    let cache_file = match File::create(cache_path) {
+        Ok(f) => f,
+        Err(e) => {
+            println_stderr!("Could not write graph cache: {}", e);
+            return;
         }
     };
+    let mut writer = BufWriter::new(cache_file);
+    if let Err(e) = cache.to_writer(&mut writer) {
+        println_stderr!("Could not write graph cache: {}", e);
+    }
+}
 
-    // Parse help
-    if matches.opt_present("h") {
-        print_usage(options, &program_executable, false);
+
+fn read_graph_source(graph_file_path: &str) -> Vec<u8> {
+    let mut graph_source = Vec::new();
+    if let Err(e) = File::open(graph_file_path).and_then(|mut f| f.read_to_end(&mut graph_source)) {
+        println_stderr!("Could not open graph file: {}", e);
         process::exit(1);
     }
+    graph_source
+}
+
 
-    let graph_file_path = matches.opt_str("graph").unwrap_or("./lfw-graph.json".to_string());
-    let json_graph_data = match parse_json_file_at_path(graph_file_path) {
+fn graph_from_source(graph_source: &[u8], format: lfw::DataFormat) -> lfw::Graph {
+    let parsed_source = String::from_utf8(graph_source.to_vec()).map_err(|e| e.to_string()).and_then(|source| {
+        format.parse(&source).map_err(|e| e.to_string())
+    });
+    let json_graph_data = match parsed_source {
         Ok(d) => d,
         Err(e) => {
-            println_stderr!("Could not open graph file: {}", e);
+            println_stderr!("Could not parse graph file: {}", e);
+            process::exit(1);
+        }
+    };
+
+    match lfw::Graph::from_json(json_graph_data) {
+        Ok(g) => g,
+        Err(e) => {
+            println_stderr!("Failed to load graph data: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+
+/// Loads the graph (the standard board unless `--graph` is given) and builds a solver for it.
+/// With `--cache`, the solver is read from that binary cache instead, unless the graph has changed
+/// since it was written, in which case it's rebuilt and the cache rewritten.
+fn load_solver(matches: &Matches) -> lfw::Solver {
+    let graph_file_path = matches.opt_str("graph");
+    let (graph_source, graph_format) = match graph_file_path {
+        Some(ref path) => (read_graph_source(path), data_format_for_path(path, "graph-format", matches)),
+        None => (lfw::STANDARD_GRAPH_JSON.as_bytes().to_vec(), lfw::DataFormat::Json),
+    };
+
+    let source_hash = lfw::SolverCache::hash_source(&graph_source);
+    let cache_path = matches.opt_str("cache");
+
+    if let Some(solver) = cache_path.as_ref().and_then(|p| read_cache_at_path(p, &source_hash)) {
+        return solver;
+    }
+
+    let graph = graph_from_source(&graph_source, graph_format);
+    let cache = lfw::SolverCache::new(lfw::Solver::from_graph(graph), source_hash);
+    if let Some(p) = cache_path {
+        write_cache_at_path(p, &cache);
+    }
+    cache.into_solver()
+}
+
+
+fn validate_graph(graph: &lfw::Graph) {
+    let findings = graph.validate();
+    if findings.is_empty() {
+        println!("No problem found in the graph.");
+        return;
+    }
+
+    println!("Found {} problem(s) in the graph:", findings.len());
+    for finding in &findings {
+        println!("{}", finding);
+    }
+    process::exit(1);
+}
+
+
+fn diff_graphs(matches: &Matches) {
+    let (old_graph_path, new_graph_path) = match (matches.free.get(1), matches.free.get(2)) {
+        (Some(old), Some(new)) => (old, new),
+        _ => {
+            println_stderr!("The diff command needs two graph files to compare");
             process::exit(1);
         }
     };
+    let old_graph = graph_from_source(&read_graph_source(old_graph_path), data_format_for_path(old_graph_path, "graph-format", matches));
+    let new_graph = graph_from_source(&read_graph_source(new_graph_path), data_format_for_path(new_graph_path, "graph-format", matches));
 
+    let diff = lfw::GraphDiff::between(&old_graph, &new_graph);
+    if diff.is_empty() {
+        println!("No connection changed between the graphs.");
+        return;
+    }
+
+    println!("{} connection(s) changed:", diff.connection_changes().len());
+    for (change, distance_changes) in diff.connection_changes() {
+        println!("{} (changes {} distance(s) on its own)", change, distance_changes.len());
+    }
+
+    println!("{} distance(s) changed:", diff.distance_changes().len());
+    for distance_change in diff.distance_changes() {
+        println!("{}", distance_change);
+    }
+}
+
+
+fn parse_location_argument(matches: &Matches, arg_idx: usize, graph: &lfw::Graph) -> u32 {
+    match matches.free.get(arg_idx).map(|l| l.parse::<u32>()) {
+        Some(Ok(l)) if l >= 1 && l <= graph.nb_locations() => l,
+        Some(_) => {
+            println_stderr!("Invalid location (must be between 1 and {})", graph.nb_locations());
+            process::exit(1);
+        }
+        None => {
+            println_stderr!("Missing location for the graph command");
+            process::exit(1);
+        }
+    }
+}
+
+
+fn format_locations(locations: &lfw::LocationSet) -> String {
+    locations.iter().map(|l| l.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+
+// Answers questions about the board: `neighbours LOCATION`, `distance FROM TO` and `routes FROM TO`
+fn query_graph(solver: &lfw::Solver, matches: &Matches) {
+    let graph = solver.graph();
+    match matches.free.get(1).map(|q| q.as_str()) {
+        Some("neighbours") => {
+            let location = parse_location_argument(matches, 2, graph);
+            // unwrap() because the location was checked to be on the board
+            println!("Regular: {}", format_locations(graph.connection_set_for_location(&location).unwrap()));
+            println!("Alleyway: {}", format_locations(graph.alleyway_connection_set_for_location(&location).unwrap()));
+            println!("Carriage: {}", format_locations(&graph.carriage_connections_for_location(&location).unwrap()));
+        }
+        Some("distance") => {
+            let location = parse_location_argument(matches, 2, graph);
+            let destination = parse_location_argument(matches, 3, graph);
+            let format_distance = |distance: Option<u32>| distance.map_or("unreachable".to_string(), |d| d.to_string());
+            println!("Any move: {}", format_distance(solver.shortest_distance(&location, &destination)));
+            println!("Regular moves only: {}",
+                     format_distance(solver.shortest_distance_for_type(&location, &destination, lfw::ConnectionType::Regular)));
+            println!("Alleyways only: {}",
+                     format_distance(solver.shortest_distance_for_type(&location, &destination, lfw::ConnectionType::Alleyway)));
+        }
+        Some("routes") => {
+            let location = parse_location_argument(matches, 2, graph);
+            let destination = parse_location_argument(matches, 3, graph);
+            let routes = solver.shortest_routes(&location, &destination);
+            if routes.is_empty() {
+                println!("No route from {} to {}.", location, destination);
+                return;
+            }
+            println!("{} shortest route(s) of {} move(s) (\"->\" regular move, \"~>\" alleyway):",
+                     routes.len(), routes[0].len() - 1);
+            for route in &routes {
+                let mut formatted_route = route[0].to_string();
+                for step in route.windows(2) {
+                    // unwrap() because routes only contain valid locations
+                    let is_regular = graph.connection_set_for_location(&step[0]).unwrap().contains(&step[1]);
+                    formatted_route.push_str(if is_regular { " -> " } else { " ~> " });
+                    formatted_route.push_str(&step[1].to_string());
+                }
+                println!("{}", formatted_route);
+            }
+        }
+        _ => {
+            println_stderr!("Unknown graph query (must be neighbours LOCATION, distance FROM TO or routes FROM TO)");
+            process::exit(1);
+        }
+    }
+}
+
+
+fn export_graph_to_dot(graph: &lfw::Graph, matches: &Matches) {
+    let highlighted = match matches.opt_str("highlight") {
+        None => lfw::LocationSet::new(),
+        Some(locations) => {
+            let parsed: Result<Vec<u32>, _> = locations.split(',').map(|l| l.trim().parse::<u32>()).collect();
+            match parsed {
+                Ok(ref l) if l.iter().all(|l| *l >= 1 && *l <= graph.nb_locations()) => l.iter().collect(),
+                _ => {
+                    println_stderr!("Invalid locations to highlight (must be a comma-separated list of locations)");
+                    process::exit(1);
+                }
+            }
+        }
+    };
+    print!("{}", graph.to_dot_highlighting(&highlighted));
+}
+
+
+// Rewrites the game data file in the current version of the format, or writes it to `--output`.
+// Nights written as event logs are left as they are, as they aren't versioned.
+fn upgrade_game_data(graph: &lfw::Graph, matches: &Matches) {
     let game_data_path = matches.opt_str("game-data").unwrap_or("./lfw-game-data.json".to_string());
-    let json_night_data = match parse_json_file_at_path(game_data_path) {
+    let game_data_format = data_format_for_path(&game_data_path, "data-format", matches);
+    let json_game_data = match parse_game_data_at_path(&game_data_path, game_data_format) {
         Ok(d) => d,
         Err(e) => {
             println_stderr!("Could not open game data file: {}", e);
@@ -77,15 +315,129 @@ fn main() {
         }
     };
 
-    let graph = match lfw::Graph::from_json(json_graph_data) {
-        Ok(g) => g,
+    let (json_nights, is_game) = match json_game_data {
+        JsonValue::Array(json_nights) => (json_nights, true),
+        json_night => (vec![json_night], false),
+    };
+
+    let mut upgraded_nights = Vec::new();
+    for json_night in json_nights {
+        if json_night.get(lfw::NightEventsKey).is_some() {
+            upgraded_nights.push(json_night);
+            continue;
+        }
+        // Going through `Night` makes sure the upgraded data is valid
+        match lfw::Night::from_json(json_night, graph.nb_locations()).and_then(|n| n.to_json()) {
+            Ok(n) => upgraded_nights.push(n),
+            Err(e) => {
+                println_stderr!("Failed to upgrade game data: {}", e);
+                process::exit(1);
+            }
+        }
+    }
+
+    // Writing TOML or YAML back would lose the comments and layout of the file, so those are only
+    // written to another file
+    let output_path = match matches.opt_str("output") {
+        Some(path) => path,
+        None if game_data_format == lfw::DataFormat::Json => game_data_path.clone(),
+        None => {
+            println_stderr!("Upgrading {} in place would lose its comments and layout, use --output to write it to another file",
+                            game_data_path);
+            process::exit(1);
+        }
+    };
+    let output_format = if matches.opt_present("output") { lfw::DataFormat::from_path(&output_path) } else { game_data_format };
+
+    let upgraded_game_data = match (is_game, output_format) {
+        (true, lfw::DataFormat::Toml) => {
+            let mut game = serde_json::Map::new();
+            game.insert(GAME_NIGHTS_KEY.to_string(), JsonValue::Array(upgraded_nights));
+            JsonValue::Object(game)
+        },
+        (true, _) => JsonValue::Array(upgraded_nights),
+        (false, _) => upgraded_nights.remove(0),
+    };
+
+    let written = output_format.write(&upgraded_game_data).map_err(|e| e.to_string()).and_then(|data| {
+        write_file_atomically(&output_path, data.as_bytes()).map_err(|e| e.to_string())
+    });
+    match written {
+        Ok(_) => println!("Upgraded game data to version {} in {}.", lfw::GameDataVersion, output_path),
         Err(e) => {
-            println_stderr!("Failed to load graph data: {}", e);
+            println_stderr!("Could not write game data file: {}", e);
+            process::exit(1);
+        }
+    }
+}
+
+
+// Police positions only matter on graphs with crossings, warn instead of silently ignoring them
+fn warn_if_police_ignored(graph: &lfw::Graph, night: &lfw::Night) {
+    let has_police = (1..=*night.jack_nb_moves()).any(|t| !night.police_crossings_for_turn(&t).is_empty());
+    if has_police && !graph.has_crossings() {
+        println_stderr!("Warning: the graph has no crossings, police positions are ignored");
+    }
+}
+
+fn solve(solver: lfw::Solver, matches: &Matches) {
+    let game_data_path = matches.opt_str("game-data").unwrap_or("./lfw-game-data.json".to_string());
+    let game_data_format = data_format_for_path(&game_data_path, "data-format", matches);
+    let json_night_data = match parse_game_data_at_path(&game_data_path, game_data_format) {
+        Ok(d) => d,
+        Err(e) => {
+            println_stderr!("Could not open game data file: {}", e);
             process::exit(1);
         }
     };
 
-    let night = match lfw::Night::from_json(json_night_data) {
+    let nb_threads = match matches.opt_str("threads").map(|t| t.parse::<usize>()) {
+        None => None,
+        Some(Ok(t)) if t > 0 => Some(t),
+        Some(_) => {
+            println_stderr!("Invalid number of threads (must be a positive integer)");
+            process::exit(1);
+        }
+    };
+
+    let nb_locations = solver.graph().nb_locations();
+
+    // A game data file can either contain a single night, or an array with all the nights of a game
+    if let JsonValue::Array(json_nights) = json_night_data {
+        let mut nights = Vec::new();
+        for json_night in json_nights {
+            match lfw::Night::from_json(json_night, nb_locations) {
+                Ok(n) => {
+                    warn_if_police_ignored(solver.graph(), &n);
+                    nights.push(n)
+                }
+                Err(e) => {
+                    println_stderr!("Failed to load game data: {}", e);
+                    process::exit(1);
+                }
+            }
+        }
+
+        let game_hideouts = match nb_threads {
+            Some(t) => solver.solve_game_parallel(nights, t),
+            None => solver.solve_game(nights),
+        };
+        match game_hideouts {
+            Some(hideouts) => {
+                println!("Potential hideouts:");
+                for hideout in &hideouts {
+                    println!("{:?}", hideout);
+                }
+            }
+            None => {
+                println_stderr!("No solution found. Have you entered the data correctly?");
+                process::exit(1);
+            }
+        }
+        return;
+    }
+
+    let night = match lfw::Night::from_json(json_night_data, nb_locations) {
         Ok(g) => g,
         Err(e) => {
             println_stderr!("Failed to load game data: {}", e);
@@ -93,25 +445,21 @@ fn main() {
         }
     };
     // println!("{:?}", night);
+    warn_if_police_ignored(solver.graph(), &night);
 
-    let solver = lfw::Solver::from_graph(graph);
-
-    match solver.solve_night(night) {
-        Some(solutions) => {
-            // println!("Jack potentially used the following paths:");
-            // for solution in &solutions {
-            //     println!("{:?}", solution);
-            // }
-            let zero = 0;
-            let mut hideout_set = HashSet::new();
-            for solution in &solutions {
-                hideout_set.insert(solution.last().unwrap_or(&zero));
-            }
-            let mut hideouts = Vec::from_iter(hideout_set.into_iter());
-            hideouts.sort();
-            println!("Potential hideouts:");
-            for hideout in &hideouts {
-                println!("{:?}", hideout);
+    let hideout_distribution = match nb_threads {
+        Some(t) => solver.count_paths_per_hideout_parallel(&night, t),
+        None => solver.count_paths_per_hideout(&night),
+    };
+
+    match hideout_distribution {
+        Some(distribution) => {
+            println!("Potential hideouts ({} possible paths):", distribution.nb_paths());
+            for (hideout, probability) in distribution.sorted_by_likelihood() {
+                println!("{:>3}: {:>6.2}% ({} paths)",
+                         hideout,
+                         probability * 100.0,
+                         distribution.nb_paths_for_hideout(&hideout));
             }
         }
         None => {
@@ -120,3 +468,70 @@ fn main() {
         }
     }
 }
+
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    let program_executable = args[0].clone();
+
+    let mut options = Options::new();
+    options.optflag("h", "help", "Display this help message");
+    options.optopt("g", "graph", "Path to a file representing the LfW graph (defaults to the standard board)", "GRAPH");
+    options.optopt("d",
+                   "game-data",
+                   "Path to a file containing the game data (a night, either aggregated or as an event log, or an array of nights)",
+                   "GAME_DATA");
+    options.optopt("t",
+                   "threads",
+                   "Number of threads used to count Jack's paths",
+                   "THREADS");
+    options.optopt("c",
+                   "cache",
+                   "Path to a binary cache of the built graph, to make loading it faster (rewritten whenever the graph changes)",
+                   "CACHE");
+    options.optopt("",
+                   "highlight",
+                   "Comma-separated locations to highlight when exporting the graph with the dot command",
+                   "LOCATIONS");
+    options.optopt("",
+                   "graph-format",
+                   "Format of the graph files: json, toml or yaml (guessed from their extension by default)",
+                   "FORMAT");
+    options.optopt("",
+                   "data-format",
+                   "Format of the game data file: json, toml or yaml (guessed from its extension by default)",
+                   "FORMAT");
+    options.optopt("o",
+                   "output",
+                   "File the upgrade command writes the upgraded game data to, in the format of its extension (defaults to rewriting JSON game data in place)",
+                   "OUTPUT");
+
+    let matches = match options.parse(&args[1..]) {
+        Ok(m) => m,
+        Err(e) => {
+            println_stderr!("{}", e);
+            print_usage(options, &program_executable, true);
+            process::exit(1);
+        }
+    };
+
+    // Parse help
+    if matches.opt_present("h") {
+        print_usage(options, &program_executable, false);
+        process::exit(1);
+    }
+
+    match matches.free.first().map(|c| c.as_str()) {
+        None | Some("solve") => solve(load_solver(&matches), &matches),
+        Some("validate") => validate_graph(load_solver(&matches).graph()),
+        Some("dot") => export_graph_to_dot(load_solver(&matches).graph(), &matches),
+        Some("diff") => diff_graphs(&matches),
+        Some("graph") => query_graph(&load_solver(&matches), &matches),
+        Some("upgrade") => upgrade_game_data(load_solver(&matches).graph(), &matches),
+        Some(command) => {
+            println_stderr!("Unknown command: {}", command);
+            print_usage(options, &program_executable, true);
+            process::exit(1);
+        }
+    }
+}
@@ -0,0 +1,43 @@
+extern crate lfw_solver;
+extern crate serde_json;
+
+use lfw_solver::lfw::{CacheError, Graph, Solver, SolverCache};
+
+
+fn cache_round_trip(solver: Solver) -> Result<SolverCache, CacheError> {
+    let mut written = Vec::new();
+    SolverCache::new(solver, 42).to_writer(&mut written).unwrap();
+    SolverCache::from_reader(&mut &written[..])
+}
+
+#[test]
+fn cache_round_trips() {
+    let cache = cache_round_trip(Solver::from_graph(Graph::standard())).unwrap();
+    assert_eq!(*cache.source_hash(), 42);
+    assert_eq!(cache.solver().graph().nb_locations(), 195);
+    assert!(cache.solver().is_consistent());
+}
+
+#[test]
+fn inconsistent_cached_solver_is_rejected() {
+    let mut json = serde_json::to_value(Solver::from_graph(Graph::standard())).unwrap();
+    json["shortest_distances"].as_array_mut().unwrap().pop();
+    let solver: Solver = serde_json::from_value(json).unwrap();
+
+    match cache_round_trip(solver) {
+        Err(CacheError::InconsistentSolver) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn inconsistent_cached_graph_is_rejected() {
+    let mut json = serde_json::to_value(Solver::from_graph(Graph::standard())).unwrap();
+    json["graph"]["regular_connections"][0] = serde_json::Value::from(vec![1000]);
+    let solver: Solver = serde_json::from_value(json).unwrap();
+
+    match cache_round_trip(solver) {
+        Err(CacheError::InconsistentSolver) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
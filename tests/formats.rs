@@ -0,0 +1,82 @@
+extern crate lfw_solver;
+#[macro_use]
+extern crate serde_json;
+
+use lfw_solver::lfw::{DataFormat, DataFormatError, Graph, Night};
+use std::fs::File;
+
+
+const TOML_GRAPH: &'static str = r#"
+# Locations are keys, so they're strings in TOML
+[1]
+regular_moves = [2]
+alleyway_moves = []
+crossings = [1]
+
+[2]
+regular_moves = [1]
+alleyway_moves = []
+crossings = [1]
+district = "east"
+"#;
+
+const YAML_GRAPH: &'static str = "
+# Locations can be plain numbers in YAML
+1:
+  regular_moves: [2]
+  alleyway_moves: []
+  coordinates: [1.5, 2]
+2:
+  regular_moves: [1]
+  alleyway_moves: []
+";
+
+#[test]
+fn graph_from_toml() {
+    let graph = Graph::from_toml(TOML_GRAPH).unwrap();
+    assert_eq!(graph.nb_locations(), 2);
+    assert_eq!(graph.connections_for_location(&1), Some(&vec![2]));
+    assert_eq!(graph.crossings_between_locations(&1, &2), vec![1]);
+    assert_eq!(graph.district_for_location(&2), Some("east"));
+}
+
+#[test]
+fn graph_from_yaml() {
+    let graph = Graph::from_yaml(YAML_GRAPH).unwrap();
+    assert_eq!(graph.nb_locations(), 2);
+    assert_eq!(graph.connections_for_location(&2), Some(&vec![1]));
+    assert_eq!(graph.coordinates_for_location(&1), Some((1.5, 2.0)));
+}
+
+#[test]
+fn night_from_toml_and_yaml() {
+    let json_night: serde_json::Value = serde_json::from_reader(File::open("lfw-game-data.json").unwrap()).unwrap();
+    let night = Night::from_json(json_night.clone(), 195).unwrap();
+
+    let toml = DataFormat::Toml.write(&json_night).unwrap();
+    assert_eq!(Night::from_toml(&toml, 195).unwrap(), night);
+    let yaml = DataFormat::Yaml.write(&json_night).unwrap();
+    assert_eq!(Night::from_yaml(&yaml, 195).unwrap(), night);
+}
+
+#[test]
+fn yaml_numeric_keys_become_json_strings() {
+    let json = DataFormat::Yaml.parse("1: {a: 2}\n2.5: [true, null]\nname: x\n").unwrap();
+    assert_eq!(json, json!({"1": {"a": 2}, "2.5": [true, null], "name": "x"}));
+}
+
+#[test]
+fn yaml_keys_must_be_strings_or_numbers() {
+    match DataFormat::Yaml.parse("[1, 2]: x\n") {
+        Err(DataFormatError::UnsupportedYamlKey) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn formats_from_names_and_paths() {
+    assert_eq!(DataFormat::from_name("YML"), Some(DataFormat::Yaml));
+    assert_eq!(DataFormat::from_name("xml"), None);
+    assert_eq!(DataFormat::from_path("games/night.toml"), DataFormat::Toml);
+    assert_eq!(DataFormat::from_path("night"), DataFormat::Json);
+}
@@ -0,0 +1,121 @@
+extern crate lfw_solver;
+#[macro_use]
+extern crate serde_json;
+
+use lfw_solver::lfw::{ConnectionChange, ConnectionType, DistanceChange, Graph, GraphBuilder, GraphDiff, GraphError};
+use lfw_solver::lfw::{LocationSet, UnreachableDistance, ValidationFinding};
+
+
+#[test]
+fn location_keys_must_fit_a_location_number() {
+    let location = json!({"regular_moves": [], "alleyway_moves": []});
+    match Graph::from_json(json!({"1": location.clone(), "4294967295": location.clone()})) {
+        Err(GraphError::LocationNotFound(2)) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+    for key in &["0", "4294967296"] {
+        let mut json = json!({"1": location.clone()});
+        json[*key] = location.clone();
+        match Graph::from_json(json) {
+            Err(GraphError::InvalidLocationKey(ref invalid_key)) if invalid_key == key => (),
+            result => panic!("unexpected result: {:?}", result),
+        }
+    }
+}
+
+#[test]
+fn validation_reports_each_kind_of_finding() {
+    let mut builder = GraphBuilder::with_locations(6);
+    builder.add_regular_connection(1, 2)
+        .add_regular_connection(2, 3)
+        .add_regular_connection(3, 4)
+        .add_regular_connection(4, 5)
+        .add_directed_regular_connection(2, 2)
+        .add_directed_regular_connection(3, 1)
+        .add_directed_alleyway_connection(1, 5)
+        .add_alleyway_connection(5, 6);
+    assert_eq!(builder.build().unwrap().validate(), vec![
+        ValidationFinding::OneWayConnection(1, 5, ConnectionType::Alleyway),
+        ValidationFinding::ImplausibleAlleyway(1, 5, Some(4)),
+        ValidationFinding::SelfConnection(2, ConnectionType::Regular),
+        ValidationFinding::OneWayConnection(3, 1, ConnectionType::Regular),
+        ValidationFinding::ImplausibleAlleyway(5, 6, None),
+        ValidationFinding::ImplausibleAlleyway(6, 5, None),
+        ValidationFinding::IsolatedLocation(6),
+    ]);
+}
+
+#[test]
+fn validation_reports_duplicate_connections() {
+    // The builder ignores connections added twice, so duplicates can only come from a file
+    let graph = Graph::from_json(json!({
+        "1": {"regular_moves": [2, 2], "alleyway_moves": []},
+        "2": {"regular_moves": [1], "alleyway_moves": []}
+    })).unwrap();
+    assert_eq!(graph.validate(), vec![ValidationFinding::DuplicateConnection(1, 2, ConnectionType::Regular)]);
+}
+
+#[test]
+fn standard_board_has_a_crossing_for_each_regular_move() {
+    let graph = Graph::standard();
+    assert!(graph.has_crossings());
+    for finding in graph.validate() {
+        match finding {
+            ValidationFinding::RegularMoveWithoutCrossing(_, _) | ValidationFinding::UnusedCrossing(_) => {
+                panic!("unexpected finding: {}", finding)
+            },
+            _ => (),
+        }
+    }
+}
+
+#[test]
+fn dot_export_draws_each_connection_once() {
+    let mut builder = GraphBuilder::with_locations(3);
+    builder.add_regular_connection(1, 2)
+        .add_directed_alleyway_connection(2, 3)
+        .set_coordinates_for_location(1, 0.0, 1.5);
+    let mut highlighted = LocationSet::new();
+    highlighted.insert(&3);
+    assert_eq!(builder.build().unwrap().to_dot_highlighting(&highlighted), "graph lfw {
+    node [shape=circle];
+    1 [pos=\"0,1.5!\"];
+    3 [style=filled, fillcolor=red];
+    1 -- 2 [style=solid];
+    2 -- 3 [style=dashed, dir=forward];
+}
+");
+}
+
+// (location, destination, old distance, new distance) for each distance change
+fn distances(changes: &[DistanceChange]) -> Vec<(u32, u32, u32, u32)> {
+    changes.iter().map(|change| {
+        (*change.location(), *change.destination(), *change.old_distance(), *change.new_distance())
+    }).collect()
+}
+
+#[test]
+fn diff_attributes_distance_changes_to_each_connection_change() {
+    let mut old_builder = GraphBuilder::with_locations(3);
+    old_builder.add_regular_connection(1, 2).add_regular_connection(2, 3);
+    let old_graph = old_builder.build().unwrap();
+    let mut new_builder = GraphBuilder::with_locations(4);
+    new_builder.add_regular_connection(1, 2).add_regular_connection(1, 3).add_regular_connection(3, 4);
+    let new_graph = new_builder.build().unwrap();
+
+    let diff = GraphDiff::between(&old_graph, &new_graph);
+    let unreachable = UnreachableDistance;
+    let changes: Vec<_> = diff.connection_changes().iter().map(|&(change, ref distance_changes)| {
+        (change, distances(distance_changes))
+    }).collect();
+    assert_eq!(changes, vec![
+        (ConnectionChange::Added(1, 3, ConnectionType::Regular), vec![(1, 3, 2, 1)]),
+        (ConnectionChange::Removed(2, 3, ConnectionType::Regular), vec![(1, 3, 2, unreachable), (2, 3, 1, unreachable)]),
+        (ConnectionChange::Added(3, 1, ConnectionType::Regular), vec![(3, 1, 2, 1)]),
+        // Location 4 isn't on the old board, so connections to it can't be applied on their own
+        (ConnectionChange::Added(3, 4, ConnectionType::Regular), vec![]),
+        (ConnectionChange::Removed(3, 2, ConnectionType::Regular), vec![(3, 1, 2, unreachable), (3, 2, 1, unreachable)]),
+        (ConnectionChange::Added(4, 3, ConnectionType::Regular), vec![]),
+    ]);
+    assert_eq!(distances(diff.distance_changes()), vec![(1, 3, 2, 1), (2, 3, 1, 2), (3, 1, 2, 1), (3, 2, 1, 2)]);
+}
@@ -0,0 +1,74 @@
+extern crate lfw_solver;
+#[macro_use]
+extern crate serde_json;
+
+use lfw_solver::lfw::{Graph, Night};
+use std::fs::File;
+
+
+fn nb_locations() -> u32 {
+    Graph::standard().nb_locations()
+}
+
+fn assert_round_trips(night: Night) {
+    let json = night.to_json().unwrap();
+    assert_eq!(Night::from_json(json, nb_locations()).unwrap(), night);
+
+    let mut written = Vec::new();
+    night.to_writer(&mut written).unwrap();
+    let json = serde_json::from_slice(&written).unwrap();
+    assert_eq!(Night::from_json(json, nb_locations()).unwrap(), night);
+}
+
+
+#[test]
+fn bundled_game_data_round_trips() {
+    let json = serde_json::from_reader(File::open("lfw-game-data.json").unwrap()).unwrap();
+    assert_round_trips(Night::from_json(json, nb_locations()).unwrap());
+}
+
+#[test]
+fn night_with_all_fields_round_trips() {
+    let json = json!({
+        "murder_location": 9,
+        "jack_nb_moves": 5,
+        "jack_clues": [{ "location": 28, "found_on_turn": 4 }],
+        "jack_alleyways": [1],
+        "jack_carriages": [3],
+        "failed_clues_per_turn": [[], [3, 4, 5], [80]],
+        "police_per_turn": [[12, 15], [], [7]],
+        "arrests": [
+            { "location": 62, "turn": 2, "outcome": "Failed" },
+            { "location": 30, "turn": 5, "outcome": "Succeeded" }
+        ]
+    });
+    assert_round_trips(Night::from_json(json, nb_locations()).unwrap());
+}
+
+#[test]
+fn event_log_round_trips_in_the_aggregated_format() {
+    let json = json!({
+        "murder_location": 9,
+        "jack_nb_moves": 4,
+        "events": [
+            { "event": "searched", "turn": 2, "locations": [3, 4] },
+            { "event": "clue", "turn": 3, "location": 28 },
+            { "event": "alleyway", "turn": 4 }
+        ]
+    });
+    let night = Night::from_json(json, nb_locations()).unwrap();
+    assert!(night.to_json().unwrap().get("events").is_none());
+    assert_round_trips(night);
+}
+
+#[test]
+fn night_without_moves_round_trips() {
+    let json = json!({
+        "murder_location": 1,
+        "jack_nb_moves": 0,
+        "jack_clues": [],
+        "jack_alleyways": [],
+        "failed_clues_per_turn": []
+    });
+    assert_round_trips(Night::from_json(json, nb_locations()).unwrap());
+}
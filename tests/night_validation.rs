@@ -0,0 +1,104 @@
+extern crate lfw_solver;
+#[macro_use]
+extern crate serde_json;
+
+use lfw_solver::lfw::{Night, NightError};
+
+
+// A valid three-move night, with `fields` set on top of it
+fn night_with(fields: serde_json::Value) -> Result<Night, NightError> {
+    let mut json = json!({
+        "murder_location": 1, "jack_nb_moves": 3, "jack_clues": [], "jack_alleyways": [],
+        "failed_clues_per_turn": [[], [], []]
+    });
+    for (key, value) in fields.as_object().unwrap() {
+        json[key] = value.clone();
+    }
+    Night::from_json(json, 195)
+}
+
+fn invalid_field(fields: serde_json::Value) -> String {
+    match night_with(fields) {
+        Err(NightError::TurnOutOfRange(field, _, _)) | Err(NightError::LocationOutOfRange(field, _, _)) => field,
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn valid_night_is_accepted() {
+    assert!(night_with(json!({
+        "jack_clues": [{"location": 2, "found_on_turn": 3}], "police_per_turn": [[1], [], [2]]
+    })).is_ok());
+}
+
+#[test]
+fn clue_turns_are_range_checked() {
+    assert_eq!(invalid_field(json!({"jack_clues": [{"location": 2, "found_on_turn": 99}]})), "jack_clues[0].found_on_turn");
+    assert_eq!(invalid_field(json!({"jack_clues": [{"location": 2, "found_on_turn": 1}, {"location": 3, "found_on_turn": 0}]})),
+               "jack_clues[1].found_on_turn");
+}
+
+#[test]
+fn police_turns_are_range_checked() {
+    assert_eq!(invalid_field(json!({"police_per_turn": [[], [], [], [4]]})), "police_per_turn[3]");
+}
+
+#[test]
+fn locations_are_range_checked() {
+    assert_eq!(invalid_field(json!({"murder_location": 196})), "murder_location");
+    assert_eq!(invalid_field(json!({"failed_clues_per_turn": [[], [0], []]})), "failed_clues_per_turn[1][0]");
+}
+
+#[test]
+fn legacy_known_locations_become_clues_found_on_the_last_turn() {
+    let night = Night::from_json(json!({
+        "murder_location": 1, "jack_nb_moves": 3, "jack_known_locations": [2],
+        "jack_alleyways": [], "failed_clues_per_turn": [[], [], []]
+    }), 195).unwrap();
+    assert_eq!(*night.jack_clues()[0].found_on_turn(), 3);
+}
+
+#[test]
+fn moves_are_either_alleyways_or_carriages() {
+    match night_with(json!({"jack_alleyways": [0], "jack_carriages": [1, 0]})) {
+        Err(NightError::ConflictingMoveTypes(ref field, 0)) if field == "jack_carriages[1]" => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn number_of_moves_is_range_checked() {
+    match night_with(json!({"jack_nb_moves": 4000000000u32})) {
+        Err(NightError::NbMovesOutOfRange(4000000000)) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+fn event_log_error(jack_nb_moves: u32, events: serde_json::Value) -> NightError {
+    let json = json!({"murder_location": 1, "jack_nb_moves": jack_nb_moves, "events": events});
+    Night::from_json(json, 195).unwrap_err()
+}
+
+#[test]
+fn event_log_errors_point_to_their_event() {
+    match event_log_error(3, json!([{"event": "clue", "turn": 1, "location": 2}, {"event": "alleyway", "turn": 4}])) {
+        NightError::TurnOutOfRange(ref field, 4, 3) if field == "events[1].turn" => (),
+        error => panic!("unexpected error: {:?}", error),
+    }
+    match event_log_error(3, json!([{"event": "searched", "turn": 2, "locations": [3, 196]}])) {
+        NightError::LocationOutOfRange(ref field, 196, 195) if field == "events[0].locations[1]" => (),
+        error => panic!("unexpected error: {:?}", error),
+    }
+    match event_log_error(3, json!([{"event": "searched", "turn": 2, "locations": [1]}])) {
+        NightError::MurderLocationIsFailedClue(ref field) if field == "events[0].locations[0]" => (),
+        error => panic!("unexpected error: {:?}", error),
+    }
+    match event_log_error(3, json!([{"event": "alleyway", "turn": 2}, {"event": "carriage", "turn": 2}])) {
+        NightError::ConflictingMoveTypes(ref field, 2) if field == "events[1].turn" => (),
+        error => panic!("unexpected error: {:?}", error),
+    }
+    match event_log_error(4000000000, json!([])) {
+        NightError::NbMovesOutOfRange(4000000000) => (),
+        error => panic!("unexpected error: {:?}", error),
+    }
+}
@@ -0,0 +1,313 @@
+extern crate lfw_solver;
+#[macro_use]
+extern crate serde_json;
+
+use lfw_solver::lfw::{ConnectionType, Graph, GraphBuilder, GraphError, HideoutDistribution, Night, Solver, ValidationFinding};
+
+
+fn night(graph: &Graph, json: serde_json::Value) -> Night {
+    Night::from_json(json, graph.nb_locations()).unwrap()
+}
+
+fn hideouts(solver: &Solver, night: &Night) -> Vec<u32> {
+    match solver.count_paths_per_hideout(night) {
+        Some(distribution) => distribution.hideouts(),
+        None => Vec::new(),
+    }
+}
+
+// A line of regular connections 1-2-3-4-5, with an alleyway between 1 and 4
+fn line_graph() -> Graph {
+    let mut builder = GraphBuilder::with_locations(5);
+    builder.add_regular_connection(1, 2)
+        .add_regular_connection(2, 3)
+        .add_regular_connection(3, 4)
+        .add_regular_connection(4, 5)
+        .add_alleyway_connection(1, 4);
+    builder.build().unwrap()
+}
+
+// A night without any clue or special move, with `fields` set on top of it (on any board)
+fn line_night_json(murder_location: u32, jack_nb_moves: u32, fields: serde_json::Value) -> serde_json::Value {
+    let mut json = json!({
+        "murder_location": murder_location, "jack_nb_moves": jack_nb_moves, "jack_clues": [],
+        "jack_alleyways": [], "failed_clues_per_turn": vec![Vec::<u32>::new(); jack_nb_moves as usize]
+    });
+    for (key, value) in fields.as_object().unwrap() {
+        json[key] = value.clone();
+    }
+    json
+}
+
+fn line_hideouts(murder_location: u32, jack_nb_moves: u32, fields: serde_json::Value) -> Vec<u32> {
+    let graph = line_graph();
+    let night = night(&graph, line_night_json(murder_location, jack_nb_moves, fields));
+    hideouts(&Solver::from_graph(graph), &night)
+}
+
+#[test]
+fn regular_moves_follow_regular_connections() {
+    assert_eq!(line_hideouts(1, 1, json!({})), vec![2]);
+    assert_eq!(line_hideouts(3, 2, json!({})), vec![1, 3, 5]);
+}
+
+#[test]
+fn alleyways_follow_alleyway_connections() {
+    assert_eq!(line_hideouts(1, 1, json!({"jack_alleyways": [0]})), vec![4]);
+    assert_eq!(line_hideouts(2, 1, json!({"jack_alleyways": [0]})), Vec::<u32>::new());
+}
+
+#[test]
+fn carriages_make_two_regular_moves_without_going_back() {
+    assert_eq!(line_hideouts(1, 1, json!({"jack_carriages": [0]})), vec![3]);
+    assert_eq!(line_hideouts(3, 1, json!({"jack_carriages": [0]})), vec![1, 5]);
+}
+
+#[test]
+fn clues_must_be_explained_before_they_are_found() {
+    let clue_on_4 = json!({"jack_clues": [{"location": 4, "found_on_turn": 2}]});
+    assert_eq!(line_hideouts(3, 2, clue_on_4), vec![3, 5]);
+    // Being on 4 on the turn the clue is found doesn't explain it
+    let late_clue_on_4 = json!({"jack_clues": [{"location": 4, "found_on_turn": 1}]});
+    assert_eq!(line_hideouts(3, 2, late_clue_on_4), Vec::<u32>::new());
+}
+
+#[test]
+fn failed_clues_rule_out_locations_up_to_their_turn() {
+    assert_eq!(line_hideouts(3, 2, json!({"failed_clues_per_turn": [[], [5]]})), vec![1, 3]);
+    assert_eq!(line_hideouts(3, 2, json!({"failed_clues_per_turn": [[4], []]})), vec![1, 3]);
+}
+
+#[test]
+fn arrests_tell_where_jack_was_after_their_move() {
+    let failed_arrest = json!({"arrests": [{"location": 2, "turn": 1, "outcome": "Failed"}]});
+    assert_eq!(line_hideouts(3, 2, failed_arrest), vec![3, 5]);
+    let successful_arrest = json!({"arrests": [{"location": 5, "turn": 2, "outcome": "Succeeded"}]});
+    assert_eq!(line_hideouts(3, 2, successful_arrest), vec![5]);
+}
+
+// Two locations linked by both a regular connection and an alleyway, next to the same crossing
+fn crossing_graph() -> Graph {
+    let mut builder = GraphBuilder::with_locations(2);
+    builder.add_regular_connection(1, 2)
+        .add_alleyway_connection(1, 2)
+        .set_crossings_for_location(1, vec![1])
+        .set_crossings_for_location(2, vec![1]);
+    builder.build().unwrap()
+}
+
+#[test]
+fn police_blocks_regular_moves() {
+    let graph = crossing_graph();
+    let solver = Solver::from_graph(graph.clone());
+
+    let free = night(&graph, json!({
+        "murder_location": 1, "jack_nb_moves": 1, "jack_clues": [], "jack_alleyways": [],
+        "failed_clues_per_turn": [[]]
+    }));
+    assert_eq!(hideouts(&solver, &free), vec![2]);
+
+    let blocked = night(&graph, json!({
+        "murder_location": 1, "jack_nb_moves": 1, "jack_clues": [], "jack_alleyways": [],
+        "failed_clues_per_turn": [[]], "police_per_turn": [[1]]
+    }));
+    assert_eq!(hideouts(&solver, &blocked), Vec::<u32>::new());
+}
+
+#[test]
+fn police_does_not_block_alleyways() {
+    let graph = crossing_graph();
+    let solver = Solver::from_graph(graph.clone());
+
+    let night = night(&graph, json!({
+        "murder_location": 1, "jack_nb_moves": 1, "jack_clues": [], "jack_alleyways": [0],
+        "failed_clues_per_turn": [[]], "police_per_turn": [[1]]
+    }));
+    assert_eq!(hideouts(&solver, &night), vec![2]);
+}
+
+#[test]
+fn police_blocks_regular_moves_on_the_standard_board() {
+    let graph = Graph::standard();
+    let solver = Solver::from_graph(graph.clone());
+    let crossings = graph.crossings_between_locations(&1, &2);
+    assert_eq!(crossings.len(), 1);
+
+    let free = line_night_json(1, 1, json!({}));
+    assert_eq!(hideouts(&solver, &night(&graph, free)), vec![2, 6, 7, 8, 9, 24, 26, 28]);
+    let blocked = line_night_json(1, 1, json!({"police_per_turn": [crossings]}));
+    assert_eq!(hideouts(&solver, &night(&graph, blocked)), vec![6, 7, 8, 9, 24, 26, 28]);
+}
+
+#[test]
+fn crossings_are_range_checked() {
+    let json = json!({"1": {"regular_moves": [2], "alleyway_moves": [], "crossings": [0]}, "2": {"regular_moves": [1], "alleyway_moves": []}});
+    match Graph::from_json(json) {
+        Err(GraphError::InvalidCrossingValue(1, 0)) => (),
+        result => panic!("unexpected result: {:?}", result),
+    }
+}
+
+#[test]
+fn validation_reports_crossing_issues() {
+    let mut builder = GraphBuilder::with_locations(3);
+    builder.add_regular_connection(1, 2)
+        .add_regular_connection(2, 3)
+        .set_crossings_for_location(1, vec![1])
+        .set_crossings_for_location(2, vec![1])
+        .set_crossings_for_location(3, vec![2]);
+    let findings = builder.build().unwrap().validate();
+    assert_eq!(findings, vec![ValidationFinding::RegularMoveWithoutCrossing(2, 3),
+                              ValidationFinding::UnusedCrossing(2)]);
+}
+
+#[test]
+fn counting_paths_handles_more_than_64_clues() {
+    let mut builder = GraphBuilder::with_locations(3);
+    builder.add_regular_connection(1, 2).add_regular_connection(2, 3);
+    let graph = builder.build().unwrap();
+    let solver = Solver::from_graph(graph.clone());
+
+    let clues: Vec<_> = (0..65).map(|_| json!({"location": 2, "found_on_turn": 2})).collect();
+    let night = night(&graph, json!({
+        "murder_location": 1, "jack_nb_moves": 2, "jack_clues": clues, "jack_alleyways": [],
+        "failed_clues_per_turn": [[], []]
+    }));
+    let distribution = solver.count_paths_per_hideout(&night).unwrap();
+    assert_eq!(distribution.hideouts(), vec![1, 3]);
+    assert_eq!(*distribution.nb_paths(), 2);
+}
+
+#[test]
+fn parallel_counting_matches_sequential_counting() {
+    let graph = Graph::standard();
+    let solver = Solver::from_graph(graph.clone());
+    let night = night(&graph, serde_json::from_str(include_str!("../lfw-game-data.json")).unwrap());
+
+    let distribution = solver.count_paths_per_hideout(&night).unwrap();
+    for nb_threads in 2..5 {
+        let parallel_distribution = solver.count_paths_per_hideout_parallel(&night, nb_threads).unwrap();
+        assert_eq!(parallel_distribution.nb_paths(), distribution.nb_paths());
+        for hideout in distribution.hideouts() {
+            assert_eq!(parallel_distribution.nb_paths_for_hideout(&hideout), distribution.nb_paths_for_hideout(&hideout));
+        }
+    }
+}
+
+#[test]
+fn boards_can_have_any_number_of_locations() {
+    let mut builder = GraphBuilder::with_locations(300);
+    for location in 1..300 {
+        builder.add_regular_connection(location, location + 1);
+    }
+    let graph = builder.build().unwrap();
+    let solver = Solver::from_graph(graph.clone());
+    assert_eq!(solver.shortest_distance(&1, &300), Some(299));
+
+    let night = night(&graph, json!({
+        "murder_location": 299, "jack_nb_moves": 1, "jack_clues": [], "jack_alleyways": [],
+        "failed_clues_per_turn": [[]]
+    }));
+    assert_eq!(hideouts(&solver, &night), vec![298, 300]);
+}
+
+fn sorted_paths(paths: Option<Vec<Vec<u32>>>) -> Vec<Vec<u32>> {
+    let mut paths = paths.unwrap_or_default();
+    paths.sort();
+    paths
+}
+
+// Checks that all the ways of solving a night find the same paths, and count them the same way
+fn assert_solvers_agree(solver: &Solver, night_json: serde_json::Value, with_slow_solver: bool) {
+    let nb_locations = solver.graph().nb_locations();
+    let night = || Night::from_json(night_json.clone(), nb_locations).unwrap();
+
+    let paths = sorted_paths(solver.solve_night(night()));
+    assert_eq!(sorted_paths(Some(solver.paths(&night()).collect())), paths);
+    assert_eq!(sorted_paths(solver.solve_night_parallel(night(), 3)), paths);
+    if with_slow_solver {
+        assert_eq!(sorted_paths(solver.solve_night_non_recurs_slow(night())), paths);
+    }
+
+    let distribution = HideoutDistribution::from_paths(&paths);
+    for counted in [solver.count_paths_per_hideout(&night()), solver.count_paths_per_hideout_parallel(&night(), 3)] {
+        let counted = counted.unwrap_or_else(HideoutDistribution::new);
+        assert_eq!(counted.nb_paths(), distribution.nb_paths());
+        assert_eq!(counted.hideouts(), distribution.hideouts());
+        for hideout in distribution.hideouts() {
+            assert_eq!(counted.nb_paths_for_hideout(&hideout), distribution.nb_paths_for_hideout(&hideout));
+        }
+    }
+}
+
+#[test]
+fn solvers_agree_on_small_boards() {
+    let solver = Solver::from_graph(line_graph());
+    let nights = vec![
+        line_night_json(3, 4, json!({})),
+        line_night_json(1, 4, json!({"jack_alleyways": [0], "jack_carriages": [2]})),
+        line_night_json(3, 4, json!({"jack_clues": [{"location": 5, "found_on_turn": 3}]})),
+        line_night_json(2, 3, json!({"failed_clues_per_turn": [[], [4]]})),
+        line_night_json(2, 0, json!({})),
+        line_night_json(2, 1, json!({"jack_alleyways": [0]})),
+    ];
+    for night_json in nights {
+        assert_solvers_agree(&solver, night_json, true);
+    }
+}
+
+#[test]
+fn solvers_agree_on_the_standard_board() {
+    let solver = Solver::from_graph(Graph::standard());
+    let game_data: serde_json::Value = serde_json::from_str(include_str!("../lfw-game-data.json")).unwrap();
+    assert_solvers_agree(&solver, game_data, false);
+
+    let night_json = json!({
+        "murder_location": 21, "jack_nb_moves": 4, "jack_alleyways": [1], "jack_carriages": [2],
+        "jack_clues": [{"location": 40, "found_on_turn": 3}], "failed_clues_per_turn": [[22], [], [], [58]]
+    });
+    assert_solvers_agree(&solver, night_json, true);
+}
+
+#[test]
+fn games_keep_the_hideouts_consistent_with_every_night() {
+    let graph = line_graph();
+    let solver = Solver::from_graph(graph.clone());
+    let nights = |night_jsons: &[serde_json::Value]| -> Vec<Night> {
+        night_jsons.iter().map(|json| night(&graph, json.clone())).collect()
+    };
+
+    let game = [line_night_json(3, 2, json!({})), line_night_json(2, 1, json!({}))];
+    assert_eq!(solver.solve_game(nights(&game)), Some(vec![1, 3]));
+    assert_eq!(solver.solve_game_parallel(nights(&game), 3), Some(vec![1, 3]));
+
+    let game = [line_night_json(3, 2, json!({})), line_night_json(5, 1, json!({}))];
+    assert_eq!(solver.solve_game(nights(&game)), None);
+    assert_eq!(solver.solve_game_parallel(nights(&game), 3), None);
+    assert_eq!(solver.solve_game(Vec::new()), None);
+}
+
+#[test]
+fn shortest_routes_list_every_route_as_short_as_possible() {
+    let solver = Solver::from_graph(line_graph());
+    assert_eq!(solver.shortest_routes(&1, &3), vec![vec![1, 2, 3], vec![1, 4, 3]]);
+    assert_eq!(solver.shortest_routes(&2, &2), vec![vec![2]]);
+    assert_eq!(solver.shortest_distance(&1, &5), Some(2));
+    assert_eq!(solver.shortest_distance_for_type(&1, &5, ConnectionType::Regular), Some(4));
+    assert_eq!(solver.shortest_distance_for_type(&1, &4, ConnectionType::Alleyway), Some(1));
+}
+
+#[test]
+fn unreachable_locations_have_no_route() {
+    let solver = Solver::from_graph(line_graph());
+    assert_eq!(solver.shortest_distance_for_type(&2, &3, ConnectionType::Alleyway), None);
+    let disconnected = Solver::from_graph(GraphBuilder::with_locations(2).build().unwrap());
+    assert_eq!(disconnected.shortest_distance(&1, &2), None);
+    assert_eq!(disconnected.shortest_routes(&1, &2), Vec::<Vec<u32>>::new());
+
+    // Same for locations that aren't on the board
+    for &(location, destination) in &[(0, 1), (1, 6), (6, 1)] {
+        assert_eq!(solver.shortest_distance(&location, &destination), None);
+        assert_eq!(solver.shortest_routes(&location, &destination), Vec::<Vec<u32>>::new());
+    }
+}